@@ -1,6 +1,6 @@
 use std::collections::BTreeSet;
 
-use flexlayout::{Context, Id, Layout, Scalar};
+use flexlayout::{Alignment, Context, Id, Justify, Layout, Scalar, Size};
 use macroquad::prelude::*;
 
 #[macroquad::main("2d showcase")]
@@ -29,40 +29,36 @@ fn gen_ui() -> (Context<2>, BTreeSet<Id>) {
     let mut ctx = Context::<2>::new();
     let root = ctx.item_new_mut(|item| {
         item.size = [
-            Some(screen_width() as Scalar),
-            Some(screen_height() as Scalar),
+            Size::Px(screen_width() as Scalar),
+            Size::Px(screen_height() as Scalar),
         ];
         item.flags.as_parent.layout = Layout::Flex(1.try_into().unwrap());
-        item.flags.as_parent.alignment_along_axis.front = true;
-        item.flags.as_parent.alignment_along_axis.back = true;
+        item.flags.as_parent.alignment_along_axis = Justify::SpaceBetween;
     });
     let mut leaves: BTreeSet<Id> = unsafe { std::mem::zeroed() };
     let mut rows = vec![];
-    for (row_len, align_front, align_back) in [
-        (6, true, true),
-        (7, false, false),
-        (8, true, false),
-        (9, false, true),
+    for (row_len, justify, alignment) in [
+        (6, Justify::SpaceBetween, Alignment::Fill),
+        (7, Justify::Center, Alignment::Center),
+        (8, Justify::Start, Alignment::Start),
+        (9, Justify::End, Alignment::End),
     ] {
         let row = ctx.item_new_mut(|item| {
-            item.size = [None, None];
+            item.size = [Size::Auto, Size::Auto];
             item.flags.as_parent.layout = Layout::Flex(0.try_into().unwrap());
             item.margins[1].start = 20;
             item.margins[1].end = 20;
-            item.flags.as_parent.alignment_along_axis.front = align_front;
-            item.flags.as_parent.alignment_along_axis.back = align_back;
-            item.flags.as_child.alignment_cross_axis[0].front = true;
-            item.flags.as_child.alignment_cross_axis[0].back = true;
+            item.flags.as_parent.alignment_along_axis = justify;
+            item.flags.as_child.alignment_cross_axis[0] = Some(Alignment::Fill);
+            item.flags.as_parent.alignment_cross_axis[1] = alignment;
         });
         rows.push(row);
         ctx.push_back(root, row).unwrap();
         for i in 0..row_len {
             let child = ctx.item_new_mut(|item| {
-                item.size = [Some(20), Some(20 + i * 5)];
+                item.size = [Size::Px(20), Size::Px(20 + i * 5)];
                 item.margins[0].start = 10;
                 item.margins[0].end = 10;
-                item.flags.as_child.alignment_cross_axis[1].front = align_front;
-                item.flags.as_child.alignment_cross_axis[1].back = align_back;
             });
 
             ctx.push_back(row, child).unwrap();
@@ -3,3 +3,18 @@ use crate::Id;
 /// item not in context
 #[derive(Debug, Clone)]
 pub struct ItemNotFound(pub Id);
+
+/// layout could not be done
+#[derive(Debug, Clone)]
+pub enum LayoutError {
+    /// item not in context
+    ItemNotFound(Id),
+    /// child doesn't fit in a parent with `Overflow::Error`
+    Overflow(Id),
+}
+
+impl From<ItemNotFound> for LayoutError {
+    fn from(ItemNotFound(id): ItemNotFound) -> Self {
+        Self::ItemNotFound(id)
+    }
+}
@@ -1,14 +1,95 @@
 use crate::Fin;
 use crate::Scalar;
+use std::ops::Range;
 
 /// todo: add guard or lifetime constraint to make this only usable in 1 Context
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Id(pub(crate) u32);
 
+/// Space kept around an item, not part of its size
+///
+/// Negative margin pulls neighbours over the item, to overlap them
+/// |[ a [ b ]    |
+///
+/// where a.end + b.start < 0. Collapsing margins facing each other takes
+/// the biggest positive plus the most negative one. Across axis, Fill
+/// stretches the item over negative margins. An item never makes its
+/// parent's content measure less than 0
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Margin {
     pub start: Scalar,
     pub end: Scalar,
+    /// take a share of space left on the line before this item, on top of `start`
+    /// | parent             |
+    /// |[ a ][ b ]     [ c ]|
+    ///
+    /// across axis, the share of space left in the line
+    pub auto_start: bool,
+    /// take a share of space left on the line after this item, on top of `end`
+    pub auto_end: bool,
+}
+
+impl Margin {
+    /// Swap start and end, for laying out in reverse
+    pub fn reversed(self) -> Self {
+        Self {
+            start: self.end,
+            end: self.start,
+            auto_start: self.auto_end,
+            auto_end: self.auto_start,
+        }
+    }
+}
+
+/// Size of an item in one dimension
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    /// size of content
+    #[default]
+    Auto,
+    /// absolute size
+    Px(Scalar),
+    /// percent of parent's size inside its padding
+    Percent(Scalar),
+    /// numerator and denominator of parent's size inside its padding
+    Fraction(Scalar, Scalar),
+}
+
+impl Size {
+    /// Resolve against parent's size inside its padding
+    ///
+    /// None when the size comes from content
+    pub fn resolve(self, parent_inner_size: Scalar) -> Option<Scalar> {
+        let parent_inner_size = parent_inner_size as i32;
+        match self {
+            Size::Auto => None,
+            Size::Px(size) => Some(size),
+            Size::Percent(percent) => Some((parent_inner_size * percent as i32 / 100) as Scalar),
+            Size::Fraction(_, 0) => None,
+            Size::Fraction(numerator, denominator) => {
+                Some((parent_inner_size * numerator as i32 / denominator as i32) as Scalar)
+            }
+        }
+    }
+
+    /// Size depends on parent's size
+    pub fn is_relative(self) -> bool {
+        matches!(self, Size::Percent(_) | Size::Fraction(_, _))
+    }
+}
+
+/// Distance from the parent's edges inside its padding, for children taken out of flow
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Inset {
+    pub start: Option<Scalar>,
+    pub end: Option<Scalar>,
+}
+
+/// Inner spacing between an item's edges and its children
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Padding {
+    pub start: Scalar,
+    pub end: Scalar,
 }
 
 /// Mostly a rectangle
@@ -17,8 +98,29 @@ pub struct Item<const ND: usize> {
     pub flags: ItemFlags<ND>,
     /// ltrb
     pub margins: [Margin; ND],
-    /// Leave None to automatically get size
-    pub size: [Option<Scalar>; ND],
+    /// ltrb, part of size
+    pub padding: [Padding; ND],
+    /// Distance of text baseline from the start edge, for leaves with text
+    ///
+    /// Leave None to take the baseline of first child
+    pub baseline: [Option<Scalar>; ND],
+    /// Leave Auto to automatically get size
+    ///
+    /// A size set here is never changed by growing or shrinking
+    pub size: [Size; ND],
+    /// Starting size before growing or shrinking, used when `size` is Auto
+    ///
+    /// Leave None to start from the size of content
+    pub basis: [Option<Scalar>; ND],
+    /// Leave None to allow shrinking down to 0
+    pub min_size: [Option<Scalar>; ND],
+    /// Leave None to allow growing without limit
+    pub max_size: [Option<Scalar>; ND],
+    /// Keep size in proportion across dimensions, like `Some([16, 9])`
+    ///
+    /// A dimension with Auto size takes its size from an earlier dimension,
+    /// or from a later dimension with Px size. Use 0 to leave a dimension out.
+    pub aspect_ratio: Option<[Scalar; ND]>,
     pub(crate) first_child: Option<Id>,
     pub(crate) next_sibling: Option<Id>,
 }
@@ -28,13 +130,51 @@ impl<const ND: usize> Default for Item<ND> {
         Self {
             flags: Default::default(),
             margins: [Default::default(); ND],
-            size: [None; ND],
+            padding: [Default::default(); ND],
+            baseline: [None; ND],
+            size: [Size::Auto; ND],
+            basis: [None; ND],
+            min_size: [None; ND],
+            max_size: [None; ND],
+            aspect_ratio: None,
             first_child: None,
             next_sibling: None,
         }
     }
 }
 
+impl<const ND: usize> Item<ND> {
+    /// Keep size between `min_size` and `max_size`
+    ///
+    /// `min_size` wins when the two disagree
+    pub fn clamp_size(&self, dim: Fin<ND>, size: Scalar) -> Scalar {
+        let mut size = size;
+        if let Some(max_size) = self.max_size[dim.into_usize()] {
+            size = Scalar::min(size, max_size);
+        }
+        if let Some(min_size) = self.min_size[dim.into_usize()] {
+            size = Scalar::max(size, min_size);
+        }
+        size
+    }
+
+    /// The dimension this item's size is derived from by aspect ratio
+    ///
+    /// Earlier dimensions have their final size by the time a later one is
+    /// laid out, later ones only when their size is Px
+    pub fn aspect_ratio_source(&self, dim: Fin<ND>) -> Option<Fin<ND>> {
+        let ratio = self.aspect_ratio?;
+        let dim = dim.into_usize();
+        if ratio[dim] <= 0 || self.size[dim] != Size::Auto {
+            return None;
+        }
+        let source = (0..dim).find(|&k| ratio[k] > 0).or_else(|| {
+            (dim + 1..ND).find(|&k| ratio[k] > 0 && matches!(self.size[k], Size::Px(_)))
+        })?;
+        source.try_into().ok()
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct ItemFlags<const ND: usize> {
     pub as_parent: AsParentFlags<ND>,
@@ -47,6 +187,58 @@ pub enum Layout<const ND: usize> {
     #[default]
     Fixed,
     Flex(Fin<ND>),
+    /// children overlap in the same box, each aligned on its own in every
+    /// dimension by `alignment_cross_axis`
+    /// |[ a ]    |
+    /// |  [ b ]  |
+    /// |    [ c ]|
+    ///
+    /// content size is the biggest child in each dimension, see `z_index`
+    /// for which one is on top
+    Stack,
+    /// children placed in cells of tracks, by `grid_area`
+    /// |[ a    ][ b ][ c ]|
+    /// |[ d ][ e       ]  |
+    ///
+    /// tracks of each dimension, from start to end. Children placed past
+    /// the tracks given make Auto tracks
+    Grid([Vec<Track>; ND]),
+}
+
+/// Size of a row or column in a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    /// absolute size
+    Px(Scalar),
+    /// size of the biggest child in it
+    Auto,
+    /// share of space left by other tracks, by weight
+    ///
+    /// never smaller than the biggest child in it
+    Fraction(u16),
+}
+
+/// Where a child goes in a grid, in one dimension
+#[derive(Debug, Clone, Copy)]
+pub struct GridPlacement {
+    /// index of the line before the first track covered, 0 at start
+    pub line: u16,
+    /// number of tracks covered, 0 is taken as 1
+    pub span: u16,
+}
+
+impl Default for GridPlacement {
+    fn default() -> Self {
+        Self { line: 0, span: 1 }
+    }
+}
+
+impl GridPlacement {
+    /// Indices of the tracks covered
+    pub fn tracks(self) -> Range<usize> {
+        let start = self.line as usize;
+        start..start + usize::max(1, self.span as usize)
+    }
 }
 
 // impl Layout
@@ -62,15 +254,46 @@ pub struct AsParentFlags<const ND: usize> {
     pub allow_wrap: bool,
     /// wrap around line when line too long
     pub auto_wrap: bool,
-    /// start
-    /// |[ T ][ E ][ X ][ T ]      |
-    /// end
-    /// |      [ T ][ E ][ X ][ T ]|
-    /// center
-    /// |   [ T ][ E ][ X ][ T ]   |
-    /// justify
-    /// |[ T ]  [ E ]  [ X ]  [ T ]|
-    pub alignment_along_axis: Alignment,
+    /// lay children out from the end of along axis, first child at the end
+    /// |      [ T ][ X ][ E ][ T ]|
+    ///
+    /// start and end of alignment and margins swap with it
+    pub reverse_along_axis: bool,
+    /// stack wrapped lines from the end of cross axis, first line at the end
+    /// |               |
+    /// |[ d ]          |
+    /// |[ a ][ b ][ c ]|
+    ///
+    /// start and end across axis swap with it, so children align from the
+    /// end within their line too
+    pub wrap_reverse: bool,
+    /// how extra space on a line is shared, see [`Justify`]
+    pub alignment_along_axis: Justify,
+    /// how extra space across wrapped lines is shared, see [`Justify`]
+    ///
+    /// stretch makes lines thicker
+    ///
+    /// note: only with two or more lines, a single line takes the whole space
+    pub alignment_lines: Justify,
+    /// space between neighbours on a line, added on top of their margins
+    /// |[ a ]<gap>[ b ]<gap>[ c ]|
+    pub gap_along_axis: Scalar,
+    /// space between wrapped lines, added on top of their margins
+    pub gap_cross_axis: Scalar,
+    /// neighbours share the space between them, the bigger margin wins
+    /// |[ a ]<max(a.end, b.start)>[ b ]|
+    ///
+    /// otherwise margins add up, like CSS flex
+    ///
+    /// note: applies across axis too, between the margins wrapped lines
+    /// keep from their children
+    pub collapse_margins: bool,
+    /// where children sit across axis, unless they set their own
+    ///
+    /// note: in the dimension along axis, this value is ignored
+    pub alignment_cross_axis: [Alignment; ND],
+    /// what to do with children too big to fit, see [`Overflow`]
+    pub overflow: Overflow,
 }
 
 impl<const ND: usize> Default for AsParentFlags<ND> {
@@ -79,29 +302,103 @@ impl<const ND: usize> Default for AsParentFlags<ND> {
             layout: Default::default(),
             allow_wrap: true,
             auto_wrap: true,
+            reverse_along_axis: false,
+            wrap_reverse: false,
             alignment_along_axis: Default::default(),
+            alignment_lines: Default::default(),
+            gap_along_axis: 0,
+            gap_cross_axis: 0,
+            collapse_margins: true,
+            alignment_cross_axis: [Default::default(); ND],
+            overflow: Default::default(),
         }
     }
 }
 
-#[derive(Clone, Debug, Copy)]
-pub struct Alignment {
-    /// if this child will be anchored to the front of parent
+/// How extra space along axis is shared on each line
+#[derive(Clone, Debug, Copy, Default, PartialEq, Eq)]
+pub enum Justify {
+    /// |[ T ][ E ][ X ][ T ]      |
+    #[default]
+    Start,
+    /// |      [ T ][ E ][ X ][ T ]|
+    End,
+    /// |   [ T ][ E ][ X ][ T ]   |
+    Center,
+    /// |[ T ]  [ E ]  [ X ]  [ T ]|
+    SpaceBetween,
+    /// | [ T ]  [ E ]  [ X ]  [ T ] |
+    SpaceAround,
+    /// |  [ T ]  [ E ]  [ X ]  [ T ]  |
+    SpaceEvenly,
+    /// |[  T  ][  E  ][  X  ][  T  ]|
+    ///
+    /// note: children with fixed size don't grow
+    Stretch,
+}
+
+/// What to do with children too big to fit in parent
+#[derive(Clone, Debug, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// shrink children across axis to fit
+    /// | parent  |
+    /// |[ child ]|
+    ///
+    /// note: along axis, a line still too long after shrinking is left as it is
+    #[default]
+    Clamp,
+    /// keep children's size and let them stick out
+    /// | parent  |
+    /// |[ child   ]
+    ///
+    /// note: along axis, children don't shrink either
+    Visible,
+    /// grow parent to fit its content, even past its size or `max_size`
+    /// | parent    |
+    /// |[ child   ]|
+    ///
+    /// note: along axis, children have no reason to shrink then
+    Grow,
+    /// fail layout with [`crate::LayoutError::Overflow`] naming the child
+    Error,
+}
+
+/// Where a child sits in its line across axis
+#[derive(Clone, Debug, Copy, Default, PartialEq, Eq)]
+pub enum Alignment {
+    /// anchored to the front of parent
     /// | parent       |
     /// | child |
-    pub front: bool,
-    /// if this child will be anchored to the back of parent
+    #[default]
+    Start,
+    /// | parent       |
+    ///    | child |
+    Center,
+    /// anchored to the back of parent
     /// | parent       |
     ///        | child |
-    pub back: bool,
+    End,
+    /// anchored to both front and back of parent
+    /// | parent       |
+    /// |    child     |
+    Fill,
+    /// baseline lined up with the other children aligned this way
+    /// | parent       |
+    /// | Ab | ABC | a |
+    ///
+    /// note: a child without baseline uses its back edge
+    Baseline,
 }
 
-impl Default for Alignment {
-    fn default() -> Self {
-        Self {
-            front: true,
-            back: false,
-        }
+impl<const ND: usize> ItemFlags<ND> {
+    /// Where the child sits across axis, falling back to what parent gives
+    pub fn alignment_cross_axis(&self, dim: Fin<ND>, inherited: Alignment) -> Alignment {
+        self.as_child.alignment_cross_axis[dim.into_usize()].unwrap_or(inherited)
+    }
+
+    /// Whether the child starts a new line, either asked for or wrapped
+    pub fn starts_line(&self, allow_wrap: bool, auto_wrap: bool) -> bool {
+        (allow_wrap && self.as_child.wrap_me) || (auto_wrap && self.extra.wrapped)
     }
 }
 
@@ -116,25 +413,80 @@ pub struct AsChildFlags<const ND: usize> {
     /// |       | E |  | J |        |
     /// |       -----  -----        |
     ///
+    /// Leave None to follow `alignment_cross_axis` of parent
+    ///
     /// note: in the dimension along axis, this value is ignored
-    pub alignment_cross_axis: [Alignment; ND],
+    pub alignment_cross_axis: [Option<Alignment>; ND],
     /// whether it'll be wrapped to be start of a new line
     pub wrap_me: bool,
+    /// share of leftover space on the line this child takes, 0 to not grow
+    /// | parent                        |
+    /// |[ 0 ][    1    ][      2      ]|
+    pub grow: u16,
+    /// share of overflow on the line this child gives up, 0 to not shrink
+    /// | parent       |
+    /// |[ 0 ][ 1 ][2]|
+    pub shrink: u16,
+    /// take out of flow and place against parent's edges inside its padding
+    /// | parent     [x]|
+    /// |[ a ][ b ]     |
+    ///
+    /// start and end both set stretches Auto size between them,
+    /// neither set places at start
+    ///
+    /// note: takes no space in parent, and is left out when measuring parent
+    pub absolute: Option<[Inset; ND]>,
+    /// position among siblings when building lines, lower first
+    ///
+    /// siblings with the same order keep their order in the tree,
+    /// `order` doesn't change the order to paint and hit test in
+    pub order: i32,
+    /// position among siblings when painting and hit testing, higher on top
+    ///
+    /// siblings with the same z-index keep their order in the tree,
+    /// see `Context::paint_order`
+    pub z_index: i32,
+    /// cells covered in a grid, in each dimension
+    ///
+    /// note: ignored unless parent's layout is Grid
+    pub grid_area: [GridPlacement; ND],
 }
 
 impl<const ND: usize> Default for AsChildFlags<ND> {
     fn default() -> Self {
         Self {
-            alignment_cross_axis: [Default::default(); ND],
+            alignment_cross_axis: [None; ND],
             wrap_me: Default::default(),
+            grow: 0,
+            shrink: 0,
+            absolute: None,
+            order: 0,
+            z_index: 0,
+            grid_area: [Default::default(); ND],
         }
     }
 }
 
-#[derive(Default, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct ExtraFlags<const ND: usize> {
     /// item has been inserted (bit 10)
     pub inserted: bool,
-    // /// size has been explicitly set (bit 11)
-    // pub fixed: [bool; ND],
+    /// size has been explicitly set (bit 11)
+    ///
+    /// calculated on layout
+    pub fixed: [bool; ND],
+    /// starts a new line because the line before is full
+    ///
+    /// calculated on layout
+    pub wrapped: bool,
+}
+
+impl<const ND: usize> Default for ExtraFlags<ND> {
+    fn default() -> Self {
+        Self {
+            inserted: false,
+            fixed: [false; ND],
+            wrapped: false,
+        }
+    }
 }
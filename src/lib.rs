@@ -3,6 +3,8 @@
 pub mod error;
 pub mod item;
 pub mod numbers;
+#[cfg(test)]
+mod tests;
 
 pub use error::*;
 pub use item::*;
@@ -15,6 +17,8 @@ pub struct ItemWithCalcSize<const ND: usize> {
     pub item: Item<ND>,
     pub position: [Scalar; ND],
     pub size: [Scalar; ND],
+    /// distance of baseline from the start edge, if any
+    pub baseline: [Option<Scalar>; ND],
 }
 
 impl<const ND: usize> Default for ItemWithCalcSize<ND> {
@@ -23,6 +27,7 @@ impl<const ND: usize> Default for ItemWithCalcSize<ND> {
             item: Default::default(),
             position: [Default::default(); ND],
             size: [Default::default(); ND],
+            baseline: [None; ND],
         }
     }
 }
@@ -32,6 +37,7 @@ struct ExtentAndMargins<const ND: usize> {
     pub margin_start: Scalar,
     pub extent: Scalar,
     pub margin_end: Scalar,
+    pub baseline: Option<Scalar>,
     pub flags: ItemFlags<ND>,
 }
 
@@ -39,6 +45,8 @@ struct ExtentAndMargins<const ND: usize> {
 pub struct Context<const ND: usize> {
     last_id: Id,
     pub(crate) items: std::collections::BTreeMap<Id, ItemWithCalcSize<ND>>,
+    /// auto wrap found new line breaks after the cross axis was laid out
+    line_breaks_changed: bool,
 }
 
 impl<const ND: usize> Default for Context<ND> {
@@ -53,6 +61,7 @@ impl<const ND: usize> Context<ND> {
         Self {
             last_id: Id(0),
             items,
+            line_breaks_changed: false,
         }
     }
 
@@ -65,50 +74,213 @@ impl<const ND: usize> Context<ND> {
     /// Make sure you don't have cyclic reference (DAG is fine), or the program will hang
     /// 
     ///     item_id  root of the tree
-    pub fn layout_item_recursively(&mut self, item_id: Id) -> Result<(), ItemNotFound> {
+    pub fn layout_item_recursively(&mut self, item_id: Id) -> Result<(), LayoutError> {
+        self.line_breaks_changed = false;
         for i in 0..ND {
-            self.calc_size(item_id, i.try_into().unwrap())?;
+            self.calc_size(item_id, i.try_into().unwrap(), None)?;
             self.arrange(item_id, i.try_into().unwrap())?;
         }
+
+        // lines auto wrapped along a later dimension than their cross axis
+        // are only known now, so go again with them
+        if self.line_breaks_changed {
+            self.line_breaks_changed = false;
+            for i in 0..ND {
+                self.calc_size(item_id, i.try_into().unwrap(), None)?;
+                self.arrange(item_id, i.try_into().unwrap())?;
+            }
+        }
         Ok(())
     }
 
-    fn calc_size(&mut self, item_id: Id, dim: Fin<ND>) -> Result<(), ItemNotFound> {
-        // recursively call calc_size
+    /// Measure an item and its children, bottom up
+    ///
+    /// `available` is the most space a sized ancestor leaves for this item,
+    /// auto wrapping lines are measured wrapped within it
+    fn calc_size(
+        &mut self,
+        item_id: Id,
+        dim: Fin<ND>,
+        available: Option<Scalar>,
+    ) -> Result<(), ItemNotFound> {
         let parent = self.item_err(item_id)?;
+        let available = match parent.size[dim.into_usize()] {
+            Size::Px(user_size) => Some(user_size),
+            _ => available.or(parent.max_size[dim.into_usize()]),
+        };
+        let available = available.map(|available| parent.clamp_size(dim, available));
+        let padding = parent.padding[dim.into_usize()];
+        let inner_available = available.map(|available| available - padding.start - padding.end);
+
+        // recursively call calc_size
         let mut maybe_r = parent.first_child;
         while let Some(r_id) = maybe_r {
-            self.calc_size(r_id, dim)?;
+            let margin = self.item_err(r_id)?.margins[dim.into_usize()];
+            let child_available =
+                inner_available.map(|available| available - margin.start - margin.end);
+            self.calc_size(r_id, dim, child_available)?;
             let r_item = self.item_err(r_id)?;
             maybe_r = r_item.next_sibling;
         }
+        self.calc_baseline(item_id, dim)?;
+        self.calc_own_size(item_id, dim, inner_available)?;
+
+        // parent that grows to fit is never smaller than its content
+        let item = self.item_err(item_id)?;
+        if item.flags.as_parent.overflow == Overflow::Grow {
+            let padding = item.padding[dim.into_usize()];
+            let content_size = self.calc_content_size(item_id, dim, inner_available)?;
+            let size = &mut self.item_rect_mut_err(item_id)?.size[dim.into_usize()];
+            *size = Scalar::max(*size, padding.start + content_size + padding.end);
+        }
+        Ok(())
+    }
 
+    /// Size of an item from what user gives, or from children measured before
+    fn calc_own_size(
+        &mut self,
+        item_id: Id,
+        dim: Fin<ND>,
+        inner_available: Option<Scalar>,
+    ) -> Result<(), ItemNotFound> {
         let xx = self.item_rect_mut_err(item_id)?;
         let size = &mut xx.size[dim.into_usize()];
-        let flags_as_parent = xx.item.flags.as_parent.clone();
 
         // early return if size specified by user
-        if let Some(user_size) = xx.item.size[dim.into_usize()] {
-            *size = user_size;
+        // relative size is measured like auto here, and resolved by parent in `arrange`
+        let user_size = xx.item.size[dim.into_usize()];
+        xx.item.flags.extra.fixed[dim.into_usize()] = user_size != Size::Auto;
+        if let Size::Px(user_size) = user_size {
+            *size = xx.item.clamp_size(dim, user_size);
+            return Ok(());
+        }
+
+        // early return if size follows another dimension
+        if let (Some(ratio), Some(source)) =
+            (xx.item.aspect_ratio, xx.item.aspect_ratio_source(dim))
+        {
+            let source_size = match xx.item.size[source.into_usize()] {
+                Size::Px(source_size) => xx.item.clamp_size(source, source_size),
+                _ => xx.size[source.into_usize()],
+            };
+            let derived = source_size as i32 * ratio[dim.into_usize()] as i32
+                / ratio[source.into_usize()] as i32;
+            xx.item.flags.extra.fixed[dim.into_usize()] = true;
+            xx.size[dim.into_usize()] = xx.item.clamp_size(dim, derived as Scalar);
+            return Ok(());
+        }
+
+        // early return if starting size specified by user
+        if let (Size::Auto, Some(basis)) = (user_size, xx.item.basis[dim.into_usize()]) {
+            *size = xx.item.clamp_size(dim, basis);
             return Ok(());
         }
 
+        let calc_size = self.calc_content_size(item_id, dim, inner_available)?;
+
+        // dance with borrow checker
+        let xx = self.item_rect_mut_err(item_id)?;
+        let size = &mut xx.size[dim.into_usize()];
+        let padding = xx.item.padding[dim.into_usize()];
+        *size = xx
+            .item
+            .clamp_size(dim, padding.start + calc_size + padding.end);
+
+        Ok(())
+    }
+
+    /// Size of children laid out in an item, without its padding
+    fn calc_content_size(
+        &mut self,
+        item_id: Id,
+        dim: Fin<ND>,
+        inner_available: Option<Scalar>,
+    ) -> Result<Scalar, ItemNotFound> {
+        let flags_as_parent = self.item_err(item_id)?.flags.as_parent.clone();
+        let wrap_within = if flags_as_parent.auto_wrap {
+            inner_available
+        } else {
+            None
+        };
         let calc_size = match flags_as_parent.layout {
-            Layout::Fixed => self.calc_cross_axis(item_id, dim, false)?,
+            Layout::Fixed | Layout::Stack => self.calc_cross_axis(item_id, dim, false, false, 0)?,
+            Layout::Grid(ref tracks) => self
+                .grid_tracks(item_id, dim, &tracks[dim.into_usize()], None)?
+                .iter()
+                .sum(),
             Layout::Flex(along_dim) => {
                 if dim == along_dim {
-                    self.calc_along_axis(item_id, dim, flags_as_parent.allow_wrap)?
+                    self.calc_along_axis(
+                        item_id,
+                        dim,
+                        flags_as_parent.allow_wrap,
+                        wrap_within,
+                        flags_as_parent.gap_along_axis,
+                        flags_as_parent.reverse_along_axis,
+                    )?
                 } else {
-                    self.calc_cross_axis(item_id, dim, flags_as_parent.allow_wrap)?
+                    self.calc_cross_axis(
+                        item_id,
+                        dim,
+                        flags_as_parent.allow_wrap,
+                        flags_as_parent.auto_wrap,
+                        flags_as_parent.gap_cross_axis,
+                    )?
                 }
             }
         };
+        // negative margins can't make content smaller than nothing
+        Ok(Scalar::max(0, calc_size))
+    }
 
-        // dance with borrow checker
-        let xx = self.item_rect_mut_err(item_id)?;
-        let size = &mut xx.size[dim.into_usize()];
-        *size = calc_size;
+    /// Baseline given by user, or the one children on the first line line up
+    /// by, or else first child's as if it was placed at start
+    fn calc_baseline(&mut self, item_id: Id, dim: Fin<ND>) -> Result<(), ItemNotFound> {
+        let item = self.item_err(item_id)?;
+        let mut baseline = item.baseline[dim.into_usize()];
+        if baseline.is_none() {
+            let padding = item.padding[dim.into_usize()];
+            let flags_as_parent = &item.flags.as_parent;
+            let inherited = flags_as_parent.alignment_cross_axis[dim.into_usize()];
+            let children = self.flow_children(item_id)?;
+
+            // children across axis on the first line
+            let first_line_len = match flags_as_parent.layout {
+                Layout::Fixed | Layout::Stack => children.len(),
+                Layout::Flex(along_dim) if along_dim != dim => {
+                    let allow_wrap = flags_as_parent.allow_wrap;
+                    let auto_wrap = flags_as_parent.auto_wrap;
+                    let mut len = usize::min(1, children.len());
+                    while let Some(&child_id) = children.get(len) {
+                        let flags = &self.item_err(child_id)?.flags;
+                        if flags.starts_line(allow_wrap, auto_wrap) {
+                            break;
+                        }
+                        len += 1;
+                    }
+                    len
+                }
+                Layout::Flex(_) | Layout::Grid(_) => 0,
+            };
+            let first_line = &children[..first_line_len];
+            let mut lined_up = false;
+            for &child_id in first_line {
+                let flags = &self.item_err(child_id)?.flags;
+                lined_up |= flags.alignment_cross_axis(dim, inherited) == Alignment::Baseline;
+            }
 
+            if lined_up {
+                // the first child may sit lower to line up with the others
+                let (ascent, _) = self.line_baseline(dim, first_line, inherited)?;
+                baseline = Some(padding.start + ascent);
+            } else if let Some(&child_id) = children.first() {
+                let cxx = self.item_rect_err(child_id)?;
+                let margin = cxx.item.margins[dim.into_usize()];
+                baseline = cxx.baseline[dim.into_usize()]
+                    .map(|child_baseline| padding.start + margin.start + child_baseline);
+            }
+        }
+        self.item_rect_mut_err(item_id)?.baseline[dim.into_usize()] = baseline;
         Ok(())
     }
 
@@ -118,22 +290,23 @@ impl<const ND: usize> Context<ND> {
         dim: Fin<ND>,
         mut callback: impl FnMut(ExtentAndMargins<ND>) -> Result<(), ItemNotFound>,
     ) -> Result<(), ItemNotFound> {
-        self.foreach_mut_children_rect(
-            parent_id,
-            |ItemWithCalcSize {
-                 item,
-                 position: _,
-                 size: rect_size,
-             }| {
-                let r = ExtentAndMargins {
-                    flags: item.flags.clone(),
-                    margin_start: item.margins[dim.into_usize()].start,
-                    extent: rect_size[dim.into_usize()],
-                    margin_end: item.margins[dim.into_usize()].end,
-                };
-                callback(r)
-            },
-        )
+        for child_id in self.flow_children(parent_id)? {
+            let ItemWithCalcSize {
+                item,
+                position: _,
+                size: rect_size,
+                baseline: rect_baseline,
+            } = self.item_rect_err(child_id)?;
+            let r = ExtentAndMargins {
+                flags: item.flags.clone(),
+                margin_start: item.margins[dim.into_usize()].start,
+                extent: rect_size[dim.into_usize()],
+                margin_end: item.margins[dim.into_usize()].end,
+                baseline: rect_baseline[dim.into_usize()],
+            };
+            callback(r)?;
+        }
+        Ok(())
     }
 
     /// [ a ]
@@ -145,60 +318,117 @@ impl<const ND: usize> Context<ND> {
         &mut self,
         item_id: Id,
         dim: Fin<ND>,
-        respect_line_break: bool,
+        allow_wrap: bool,
+        auto_wrap: bool,
+        gap: Scalar,
     ) -> Result<Scalar, ItemNotFound> {
+        let flags_as_parent = &self.item_err(item_id)?.flags.as_parent;
+        let inherited = flags_as_parent.alignment_cross_axis[dim.into_usize()];
+        let collapse = flags_as_parent.collapse_margins;
+        // (thickness, margins all children keep) of each line
+        let mut lines: Vec<(Scalar, Margin)> = vec![];
+        let mut line_margin: Option<Margin> = None;
         let mut max_size = 0;
-        let mut hist_acc_size = 0;
+        // children lined up by baseline, above and below it
+        let mut max_ascent = 0;
+        let mut max_descent = 0;
         self.calc_extent_margins(item_id, dim, |xx| {
-            if respect_line_break && xx.flags.as_child.wrap_me {
-                hist_acc_size += max_size;
-                max_size = 0;
+            if xx.flags.starts_line(allow_wrap, auto_wrap) {
+                if let Some(margin) = line_margin.take() {
+                    lines.push((Scalar::max(max_size, max_ascent + max_descent), margin));
+                    max_size = 0;
+                    max_ascent = 0;
+                    max_descent = 0;
+                }
             }
+            let margin = line_margin.get_or_insert(Margin {
+                start: xx.margin_start,
+                end: xx.margin_end,
+                ..Default::default()
+            });
+            margin.start = Scalar::min(margin.start, xx.margin_start);
+            margin.end = Scalar::min(margin.end, xx.margin_end);
             // IMPROVE: return ExtentAndMargins, not Scalar
             // align left or right has a different
-            max_size = Scalar::max(max_size, xx.margin_start + xx.extent + xx.margin_end);
+            if xx.flags.alignment_cross_axis(dim, inherited) == Alignment::Baseline {
+                let baseline = xx.baseline.unwrap_or(xx.extent);
+                max_ascent = Scalar::max(max_ascent, xx.margin_start + baseline);
+                max_descent = Scalar::max(max_descent, xx.extent - baseline + xx.margin_end);
+            } else {
+                max_size = Scalar::max(max_size, xx.margin_start + xx.extent + xx.margin_end);
+            }
             Ok(())
         })?;
-        Ok(max_size + hist_acc_size)
+        if let Some(margin) = line_margin {
+            lines.push((Scalar::max(max_size, max_ascent + max_descent), margin));
+        }
+
+        let mut acc_size = 0;
+        let mut last_margin_end = None;
+        for (thickness, margin) in lines {
+            if let Some(last_margin_end) = last_margin_end {
+                acc_size += gap + join_margins(collapse, last_margin_end, margin.start)
+                    - last_margin_end
+                    - margin.start;
+            }
+            acc_size += thickness;
+            last_margin_end = Some(margin.end);
+        }
+        Ok(acc_size)
     }
 
     /// [ a ] [b] [  c ]
     /// <-- measure --->
+    ///
+    /// `wrap_within` is Some(space) to auto wrap lines longer than space
     fn calc_along_axis(
         &mut self,
         item_id: Id,
         dim: Fin<ND>,
         allow_wrap: bool,
+        wrap_within: Option<Scalar>,
+        gap: Scalar,
+        reverse: bool,
     ) -> Result<Scalar, ItemNotFound> {
+        let collapse = self.item_err(item_id)?.flags.as_parent.collapse_margins;
         let mut max_line_size = 0;
-        let item = self.item_err(item_id)?;
-        let mut current_child_id = item.first_child;
+        let children = self.flow_children(item_id)?;
+        let mut current = 0;
 
-        while current_child_id.is_some() {
-            let line_start = current_child_id;
+        while current < children.len() {
+            let line_start = current;
             let mut acc_line_size = 0;
             let mut last_margin_end = 0;
 
-            'arrange_one_line: while let Some(child_id) = current_child_id {
+            'arrange_one_line: while let Some(&child_id) = children.get(current) {
                 let xx = self.item_rect_mut_err(child_id)?;
                 let item = &mut xx.item;
                 let size = xx.size[dim.into_usize()];
-                let margin = item.margins[dim.into_usize()].clone();
-                let next_sibling_id = item.next_sibling;
+                let mut margin = item.margins[dim.into_usize()];
+                if reverse {
+                    margin = margin.reversed();
+                }
 
-                let first_in_line = line_start == current_child_id;
-                if (allow_wrap && item.flags.as_child.wrap_me)
-                    && !first_in_line
-                {
+                let first_in_line = line_start == current;
+                if (allow_wrap && item.flags.as_child.wrap_me) && !first_in_line {
+                    break 'arrange_one_line;
+                }
+                let mut min_inner_margin = join_margins(collapse, last_margin_end, margin.start);
+                if !first_in_line {
+                    min_inner_margin += gap;
+                }
+                // wrap before the child that doesn't fit
+                let break_early = wrap_within.is_some_and(|space| {
+                    acc_line_size + min_inner_margin + size + margin.end > space
+                });
+                if break_early && !first_in_line {
                     break 'arrange_one_line;
                 }
-                let min_inner_margin = Scalar::max(last_margin_end, margin.start);
-                
                 acc_line_size += min_inner_margin;
                 last_margin_end = margin.end;
                 acc_line_size += size;
 
-                current_child_id = next_sibling_id;
+                current += 1;
             }
             acc_line_size += last_margin_end;
             max_line_size = Scalar::max(max_line_size, acc_line_size);
@@ -206,13 +436,20 @@ impl<const ND: usize> Context<ND> {
         Ok(max_line_size)
     }
 
-    fn arrange(&mut self, item_id: Id, dim: Fin<ND>) -> Result<(), ItemNotFound> {
+    fn arrange(&mut self, item_id: Id, dim: Fin<ND>) -> Result<(), LayoutError> {
         let item = self.item_err(item_id)?;
         let flags_as_parent = item.flags.as_parent.clone();
 
+        self.resolve_children_size(item_id, dim)?;
+
         // layout direct children
         match flags_as_parent.layout {
-            Layout::Fixed => self.arrange_cross_axis(item_id, dim, false)?,
+            Layout::Fixed | Layout::Stack => {
+                self.arrange_cross_axis(item_id, dim, false, false, 0, false)?
+            }
+            Layout::Grid(ref tracks) => {
+                self.arrange_grid(item_id, dim, &tracks[dim.into_usize()])?
+            }
             Layout::Flex(along_dim) => {
                 if dim == along_dim {
                     self.arrange_along_axis(
@@ -220,12 +457,22 @@ impl<const ND: usize> Context<ND> {
                         dim,
                         flags_as_parent.allow_wrap,
                         flags_as_parent.auto_wrap,
+                        flags_as_parent.gap_along_axis,
+                        flags_as_parent.reverse_along_axis,
                     )?;
                 } else {
-                    self.arrange_cross_axis(item_id, dim, flags_as_parent.allow_wrap)?;
+                    self.arrange_cross_axis(
+                        item_id,
+                        dim,
+                        flags_as_parent.allow_wrap,
+                        flags_as_parent.auto_wrap,
+                        flags_as_parent.gap_cross_axis,
+                        flags_as_parent.wrap_reverse,
+                    )?;
                 }
             }
         }
+        self.arrange_absolute(item_id, dim)?;
 
         // recursive call to layout children's children and so on
         let item = self.item_err(item_id)?;
@@ -239,101 +486,244 @@ impl<const ND: usize> Context<ND> {
         Ok(())
     }
 
+    /// Resolve relative sizes of direct children now that parent's size is known
+    fn resolve_children_size(&mut self, parent_id: Id, dim: Fin<ND>) -> Result<(), ItemNotFound> {
+        let pxx = self.item_rect_err(parent_id)?;
+        let padding = pxx.item.padding[dim.into_usize()];
+        let inner_size = pxx.size[dim.into_usize()] - padding.start - padding.end;
+
+        self.foreach_mut_children_rect(parent_id, |xx| {
+            let user_size = xx.item.size[dim.into_usize()];
+            if user_size.is_relative() {
+                if let Some(size) = user_size.resolve(inner_size) {
+                    xx.size[dim.into_usize()] = xx.item.clamp_size(dim, size);
+                }
+            }
+            Ok(())
+        })
+    }
+
     fn arrange_along_axis(
         &mut self,
         item_id: Id,
         dim: Fin<ND>,
         allow_wrap: bool,
         auto_wrap: bool,
-    ) -> Result<(), ItemNotFound> {
+        gap: Scalar,
+        reverse: bool,
+    ) -> Result<(), LayoutError> {
         let pxx = self.item_rect_mut_err(item_id)?;
-        let offset = pxx.position[dim.into_usize()];
-        let space = pxx.size[dim.into_usize()];
+        let padding = pxx.item.padding[dim.into_usize()];
+        let offset = pxx.position[dim.into_usize()] + padding.start;
+        let space = pxx.size[dim.into_usize()] - padding.start - padding.end;
 
         let alignment = pxx.item.flags.as_parent.alignment_along_axis;
+        let overflow = pxx.item.flags.as_parent.overflow;
+        let collapse = pxx.item.flags.as_parent.collapse_margins;
+
+        let children = self.flow_children(item_id)?;
+        let mut current = 0;
+
+        // forget line breaks from last time, and remember them for cross axis
+        let mut was_wrapped = vec![];
+        for &child_id in &children {
+            let extra = &mut self.item_mut_err(child_id)?.flags.extra;
+            was_wrapped.push(extra.wrapped);
+            extra.wrapped = false;
+        }
 
-        let mut current_child_id = pxx.item.first_child;
-        let mut gaps_before: Vec<(Id, Scalar)> = vec![];
-
-        while current_child_id.is_some() {
-            let line_start = current_child_id;
+        while current < children.len() {
+            let line_start = current;
             let mut items_on_this_line = 0;
             let mut acc_line_size = 0;
             let mut last_margin_end = 0;
-
-            'arrange_one_line: while let Some(child_id) = current_child_id {
+            let mut last_auto_end = false;
+            let mut gaps_before: Vec<Scalar> = vec![];
+            // auto margins in the gap before each child, and after the last
+            let mut auto_weights: Vec<u16> = vec![];
+            let mut grow_weights: Vec<u16> = vec![];
+            let mut shrink_weights: Vec<u16> = vec![];
+            let mut stretch_weights: Vec<u16> = vec![];
+
+            'arrange_one_line: while let Some(&child_id) = children.get(current) {
                 let xx = self.item_rect_mut_err(child_id)?;
                 let item = &mut xx.item;
                 let size = xx.size[dim.into_usize()];
-                let margin = item.margins[dim.into_usize()].clone();
-                let next_sibling_id = item.next_sibling;
-
-                let first_in_line = line_start == current_child_id;
-                let break_early = acc_line_size + last_margin_end > space;
-                if ((allow_wrap && item.flags.as_child.wrap_me) || (auto_wrap && break_early))
-                    && !first_in_line
-                {
+                let mut margin = item.margins[dim.into_usize()];
+                if reverse {
+                    margin = margin.reversed();
+                }
+
+                let first_in_line = line_start == current;
+                if allow_wrap && item.flags.as_child.wrap_me && !first_in_line {
+                    break 'arrange_one_line;
+                }
+                let mut min_inner_margin = join_margins(collapse, last_margin_end, margin.start);
+                if !first_in_line {
+                    min_inner_margin += gap;
+                }
+                // wrap before the child that doesn't fit
+                let break_early = acc_line_size + min_inner_margin + size + margin.end > space;
+                if auto_wrap && break_early && !first_in_line {
+                    item.flags.extra.wrapped = true;
                     break 'arrange_one_line;
                 }
                 items_on_this_line += 1;
-                let min_inner_margin = Scalar::max(last_margin_end, margin.start);
-                gaps_before.push((child_id, min_inner_margin));
+                gaps_before.push(min_inner_margin);
+                auto_weights.push(last_auto_end as u16 + margin.auto_start as u16);
+                if item.flags.extra.fixed[dim.into_usize()] {
+                    grow_weights.push(0);
+                    shrink_weights.push(0);
+                    stretch_weights.push(0);
+                } else {
+                    grow_weights.push(item.flags.as_child.grow);
+                    shrink_weights.push(item.flags.as_child.shrink);
+                    stretch_weights.push(1);
+                }
                 acc_line_size += min_inner_margin;
                 last_margin_end = margin.end;
+                last_auto_end = margin.auto_end;
                 acc_line_size += size;
 
-                current_child_id = next_sibling_id;
+                current += 1;
             }
             acc_line_size += last_margin_end;
+            auto_weights.push(last_auto_end as u16);
 
             if items_on_this_line > 0 {
-                let extra_space = space - acc_line_size;
+                let line = &children[line_start..current];
+                let mut extra_space = space - acc_line_size;
+
+                // growing children eat up extra space, leaving none for gaps
+                // shrinking children give back space when the line overflows,
+                // unless parent lets them stick out
+                if extra_space > 0 {
+                    extra_space = self.flex_line(dim, line, &grow_weights, extra_space)?;
+                } else if extra_space < 0 && overflow != Overflow::Visible {
+                    extra_space = self.flex_line(dim, line, &shrink_weights, extra_space)?;
+                }
+                if extra_space < 0 && overflow == Overflow::Error {
+                    let child_id = self.overflowing_child(dim, line, &gaps_before, space)?;
+                    return Err(LayoutError::Overflow(child_id));
+                }
 
-                match (alignment.front, alignment.back) {
-                    // start
-                    (true, false) => {
-                        // extra space in the end, do nothing
-                    }
-                    // center
-                    (false, false) => {
-                        gaps_before[0].1 += extra_space / 2;
-                    }
-                    // end
-                    (false, true) => {
-                        // space all in front
-                        gaps_before[0].1 += extra_space;
-                    }
-                    // space-between
-                    (true, true) => {
-                        enlarge_gaps_inbetween(&mut gaps_before, extra_space);
+                // auto margins take what growing left, before alignment gets any
+                if extra_space > 0 && auto_weights.iter().any(|&w| w > 0) {
+                    let shares = share_by_weight(extra_space, &auto_weights);
+                    for (gap_before, share) in gaps_before.iter_mut().zip(shares) {
+                        *gap_before += share;
                     }
+                    extra_space = 0;
+                }
+
+                if alignment == Justify::Stretch && extra_space > 0 {
+                    extra_space = self.flex_line(dim, line, &stretch_weights, extra_space)?;
+                }
+                justify_gaps(alignment, &mut gaps_before, extra_space);
+
+                let mirror = reverse.then_some(offset + offset + space);
+                self.arrange_along_axis_range(dim, offset, mirror, line, &gaps_before)?;
+            }
+        }
+
+        // cross axis in an earlier dimension has been laid out without these
+        if dim.into_usize() > 0 {
+            for (&child_id, was_wrapped) in children.iter().zip(was_wrapped) {
+                if self.item_err(child_id)?.flags.extra.wrapped != was_wrapped {
+                    self.line_breaks_changed = true;
                 }
-                self.arrange_along_axis_range(dim, offset, &gaps_before)?;
             }
         }
 
         Ok(())
     }
 
+    /// First child on a line to stick out of `space`
+    fn overflowing_child(
+        &self,
+        dim: Fin<ND>,
+        line: &[Id],
+        gaps_before: &[Scalar],
+        space: Scalar,
+    ) -> Result<Id, ItemNotFound> {
+        let mut acc_line_size = 0;
+        for (&child_id, &gap_before) in line.iter().zip(gaps_before) {
+            acc_line_size += gap_before + self.item_rect_err(child_id)?.size[dim.into_usize()];
+            if acc_line_size > space {
+                return Ok(child_id);
+            }
+        }
+        // only the margin after the last child sticks out
+        Ok(line[line.len() - 1])
+    }
+
+    /// Resize children on a line by weight until `extra_space` is used up
+    ///
+    /// A child that hits 0 or its min/max size stops taking part, and its
+    /// share goes to the others. Returns the space left over.
+    fn flex_line(
+        &mut self,
+        dim: Fin<ND>,
+        line: &[Id],
+        weights: &[u16],
+        mut extra_space: Scalar,
+    ) -> Result<Scalar, ItemNotFound> {
+        let mut weights = weights.to_vec();
+
+        while extra_space != 0 && weights.iter().any(|&w| w > 0) {
+            let shares = share_by_weight(extra_space, &weights);
+            let mut frozen_any = false;
+
+            for (i, &child_id) in line.iter().enumerate() {
+                if weights[i] == 0 {
+                    continue;
+                }
+                let xx = self.item_rect_mut_err(child_id)?;
+                let size = &mut xx.size[dim.into_usize()];
+                let wanted = *size + shares[i];
+                let clamped = xx.item.clamp_size(dim, Scalar::max(0, wanted));
+                if clamped != wanted {
+                    weights[i] = 0;
+                    frozen_any = true;
+                }
+                extra_space -= clamped - *size;
+                *size = clamped;
+            }
+
+            if !frozen_any {
+                break;
+            }
+        }
+        Ok(extra_space)
+    }
+
+    /// Place a line of children one after another
+    ///
+    /// `mirror` is Some(start + end of line) to place them from the end instead
     fn arrange_along_axis_range(
         &mut self,
         dim: Fin<ND>,
         mut offset: Scalar,
-        gaps_before: &[(Id, Scalar)],
+        mirror: Option<Scalar>,
+        line: &[Id],
+        gaps_before: &[Scalar],
     ) -> Result<(), ItemNotFound> {
-        if gaps_before.is_empty() {
+        if line.is_empty() {
             Ok(())
         } else {
-            let (item_id, gap_before) = gaps_before[0];
-            offset += gap_before;
+            let item_id = line[0];
+            offset += gaps_before[0];
 
             let xx = self.item_rect_mut_err(item_id)?;
             let position = &mut xx.position[dim.into_usize()];
             let size = &mut xx.size[dim.into_usize()];
 
-            *position = offset;
+            *position = match mirror {
+                Some(mirror) => mirror - offset - *size,
+                None => offset,
+            };
             offset += *size;
-            self.arrange_along_axis_range(dim, offset, &gaps_before[1..])
+            self.arrange_along_axis_range(dim, offset, mirror, &line[1..], &gaps_before[1..])
         }
     }
 
@@ -342,52 +732,139 @@ impl<const ND: usize> Context<ND> {
         item_id: Id,
         dim: Fin<ND>,
         allow_wrap: bool,
-    ) -> Result<(), ItemNotFound> {
+        auto_wrap: bool,
+        gap: Scalar,
+        wrap_reverse: bool,
+    ) -> Result<(), LayoutError> {
         let pxx = self.item_rect_mut_err(item_id)?;
-        let offset = pxx.position[dim.into_usize()];
-        let space = pxx.size[dim.into_usize()];
-        let mut current_child_id = pxx.item.first_child;
-
-        // TODO: auto wrapped items not handled this way
-        // should add a calculated "wrapped" flag
+        let padding = pxx.item.padding[dim.into_usize()];
+        let offset = pxx.position[dim.into_usize()] + padding.start;
+        let space = pxx.size[dim.into_usize()] - padding.start - padding.end;
+        let flags_as_parent = pxx.item.flags.as_parent.clone();
+        let alignment = flags_as_parent.alignment_lines;
+        let inherited = flags_as_parent.alignment_cross_axis[dim.into_usize()];
+        let collapse = flags_as_parent.collapse_margins;
+        let children = self.flow_children(item_id)?;
 
         // if not allowed to wrap, then process all children in one go without backtracking
-        if !allow_wrap {
-            return self.arrange_cross_axis_range(dim, current_child_id, None, offset, space);
+        if !allow_wrap && !auto_wrap {
+            let (ascent, _) = self.line_baseline(dim, &children, inherited)?;
+            return self.arrange_cross_axis_range(
+                dim,
+                &children,
+                offset,
+                space,
+                ascent,
+                &flags_as_parent,
+            );
         }
 
-        let mut acc_cross_axis_size = 0;
+        let mut current = 0;
+        // (start, end, thickness, ascent above baseline) of each line
+        let mut lines: Vec<(usize, usize, Scalar, Scalar)> = vec![];
+        let mut gaps_before: Vec<Scalar> = vec![];
+        let mut last_line_margin_end = None;
 
-        while current_child_id.is_some() {
-            let line_start = current_child_id;
+        while current < children.len() {
+            let line_start = current;
             // current column's width
             let mut max_cross_axis_size = 0;
+            // margins all children on the line keep
+            let mut line_margin: Option<Margin> = None;
 
-            'arrange_one_line: while let Some(child_id) = current_child_id {
+            'arrange_one_line: while let Some(&child_id) = children.get(current) {
                 let xx = self.item_rect_mut_err(child_id)?;
                 let item = &mut xx.item;
                 let size = xx.size[dim.into_usize()];
-                let margin = item.margins[dim.into_usize()].clone();
+                let margin = item.margins[dim.into_usize()];
                 let size_with_margin = size + margin.start + margin.end;
-                let next_sibling_id = item.next_sibling;
 
-                if line_start != current_child_id && item.flags.as_child.wrap_me {
+                if line_start != current && item.flags.starts_line(allow_wrap, auto_wrap) {
                     break 'arrange_one_line;
                 }
 
-                max_cross_axis_size = Scalar::max(max_cross_axis_size, size_with_margin);
+                if item.flags.alignment_cross_axis(dim, inherited) != Alignment::Baseline {
+                    max_cross_axis_size = Scalar::max(max_cross_axis_size, size_with_margin);
+                }
+                let line_margin = line_margin.get_or_insert(margin);
+                line_margin.start = Scalar::min(line_margin.start, margin.start);
+                line_margin.end = Scalar::min(line_margin.end, margin.end);
+
+                current += 1;
+            }
+
+            let line = &children[line_start..current];
+            let (ascent, descent) = self.line_baseline(dim, line, inherited)?;
+            max_cross_axis_size = Scalar::max(max_cross_axis_size, ascent + descent);
+
+            let line_margin = line_margin.unwrap_or_default();
+            gaps_before.push(match last_line_margin_end {
+                // margins facing each other are already in both lines
+                Some(last_margin_end) => {
+                    gap + join_margins(collapse, last_margin_end, line_margin.start)
+                        - last_margin_end
+                        - line_margin.start
+                }
+                None => 0,
+            });
+            last_line_margin_end = Some(line_margin.end);
+            lines.push((line_start, current, max_cross_axis_size, ascent));
+        }
 
-                current_child_id = next_sibling_id;
+        // a single line takes the whole space, like when not wrapping
+        if lines.len() <= 1 {
+            let (ascent, _) = self.line_baseline(dim, &children, inherited)?;
+            self.arrange_cross_axis_range(dim, &children, offset, space, ascent, &flags_as_parent)?;
+            if wrap_reverse {
+                self.mirror_cross_axis_range(dim, &children, offset, space)?;
             }
+            return Ok(());
+        }
+
+        // spread lines over the space left
+        let thickness_sum: Scalar = lines.iter().map(|&(_, _, thickness, _)| thickness).sum();
+        let extra_space = space - thickness_sum - gaps_before.iter().sum::<Scalar>();
+        if extra_space < 0 && flags_as_parent.overflow == Overflow::Error {
+            let mut acc_cross_axis_size = 0;
+            for (&(line_start, _, thickness, _), gap_before) in lines.iter().zip(&gaps_before) {
+                acc_cross_axis_size += gap_before + thickness;
+                if acc_cross_axis_size > space {
+                    return Err(LayoutError::Overflow(children[line_start]));
+                }
+            }
+        }
+        if alignment == Justify::Stretch && extra_space > 0 {
+            let pieces = chop(extra_space, lines.len() as Scalar);
+            for (line, piece) in lines.iter_mut().zip(pieces) {
+                line.2 += piece;
+            }
+        }
+        justify_gaps(alignment, &mut gaps_before, extra_space);
 
+        let mut acc_cross_axis_size = 0;
+        for (&(line_start, line_end, thickness, ascent), gap_before) in
+            lines.iter().zip(gaps_before)
+        {
+            acc_cross_axis_size += gap_before;
+            let line_offset = if wrap_reverse {
+                offset + space - acc_cross_axis_size - thickness
+            } else {
+                offset + acc_cross_axis_size
+            };
+            // children align within their own line
             self.arrange_cross_axis_range(
                 dim,
-                line_start,
-                current_child_id,
-                offset + acc_cross_axis_size,
-                space,
+                &children[line_start..line_end],
+                line_offset,
+                thickness,
+                ascent,
+                &flags_as_parent,
             )?;
-            acc_cross_axis_size += max_cross_axis_size;
+            if wrap_reverse {
+                let line = &children[line_start..line_end];
+                self.mirror_cross_axis_range(dim, line, line_offset, thickness)?;
+            }
+            acc_cross_axis_size += thickness;
         }
 
         // let pxx = self.item_rect_mut_err(item_id)?;
@@ -398,57 +875,295 @@ impl<const ND: usize> Context<ND> {
         Ok(())
     }
 
-    fn arrange_cross_axis_range(
+    /// Align each child within the cells it covers
+    fn arrange_grid(
         &mut self,
+        item_id: Id,
         dim: Fin<ND>,
-        maybe_start_item_id: Option<Id>,
-        end_before_id: Option<Id>,
+        tracks: &[Track],
+    ) -> Result<(), LayoutError> {
+        let pxx = self.item_rect_err(item_id)?;
+        let padding = pxx.item.padding[dim.into_usize()];
+        let offset = pxx.position[dim.into_usize()] + padding.start;
+        let space = pxx.size[dim.into_usize()] - padding.start - padding.end;
+        let flags_as_parent = pxx.item.flags.as_parent.clone();
+        let inherited = flags_as_parent.alignment_cross_axis[dim.into_usize()];
+
+        // where each line is, from start
+        let mut lines = vec![0];
+        for size in self.grid_tracks(item_id, dim, tracks, Some(space))? {
+            lines.push(lines[lines.len() - 1] + size);
+        }
+
+        for child_id in self.flow_children(item_id)? {
+            let area = self.item_err(child_id)?.flags.as_child.grid_area[dim.into_usize()];
+            let cell = area.tracks();
+            let cell_offset = offset + lines[cell.start];
+            let cell_size = lines[cell.end] - lines[cell.start];
+            let (ascent, _) = self.line_baseline(dim, &[child_id], inherited)?;
+            self.arrange_cross_axis_range(
+                dim,
+                &[child_id],
+                cell_offset,
+                cell_size,
+                ascent,
+                &flags_as_parent,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Size of each track of a grid in one dimension
+    ///
+    /// `space` is Some(inner size) when arranging, for fraction tracks to
+    /// share what the others leave
+    fn grid_tracks(
+        &self,
+        item_id: Id,
+        dim: Fin<ND>,
+        tracks: &[Track],
+        space: Option<Scalar>,
+    ) -> Result<Vec<Scalar>, ItemNotFound> {
+        // (tracks covered, size with margins) of each child
+        let mut areas = vec![];
+        for child_id in self.flow_children(item_id)? {
+            let xx = self.item_rect_err(child_id)?;
+            let margin = xx.item.margins[dim.into_usize()];
+            let cell = xx.item.flags.as_child.grid_area[dim.into_usize()].tracks();
+            areas.push((cell, margin.start + xx.size[dim.into_usize()] + margin.end));
+        }
+        // children covering fewer tracks size them first, stable
+        areas.sort_by_key(|(cell, _)| cell.len());
+
+        let count = areas
+            .iter()
+            .map(|(cell, _)| cell.end)
+            .fold(tracks.len(), usize::max);
+        let tracks: Vec<Track> = (0..count)
+            .map(|i| tracks.get(i).copied().unwrap_or(Track::Auto))
+            .collect();
+        let mut sizes: Vec<Scalar> = tracks
+            .iter()
+            .map(|&track| match track {
+                Track::Px(size) => size,
+                Track::Auto | Track::Fraction(_) => 0,
+            })
+            .collect();
+
+        // grow tracks to fit what's in them, spread over all but Px ones
+        for (cell, size) in areas {
+            let growable: Vec<usize> = cell
+                .clone()
+                .filter(|&i| !matches!(tracks[i], Track::Px(_)))
+                .collect();
+            let missing = size - cell.map(|i| sizes[i]).sum::<Scalar>();
+            if missing > 0 && !growable.is_empty() {
+                for (i, piece) in growable.iter().zip(chop(missing, growable.len() as Scalar)) {
+                    sizes[*i] += piece;
+                }
+            }
+        }
+
+        // fraction tracks share what other tracks leave
+        if let Some(space) = space {
+            let mut weights: Vec<u16> = tracks
+                .iter()
+                .map(|&track| match track {
+                    Track::Fraction(weight) => weight,
+                    Track::Px(_) | Track::Auto => 0,
+                })
+                .collect();
+            loop {
+                let others: Scalar = sizes
+                    .iter()
+                    .zip(&weights)
+                    .filter(|&(_, &weight)| weight == 0)
+                    .map(|(&size, _)| size)
+                    .sum();
+                let shares = share_by_weight(Scalar::max(0, space - others), &weights);
+                // a track with content bigger than its share keeps its size,
+                // and the rest share again what it leaves
+                let mut frozen = false;
+                for ((&size, share), weight) in sizes.iter().zip(&shares).zip(&mut weights) {
+                    if *weight > 0 && size > *share {
+                        *weight = 0;
+                        frozen = true;
+                    }
+                }
+                if !frozen {
+                    for ((size, share), &weight) in sizes.iter_mut().zip(shares).zip(&weights) {
+                        if weight > 0 {
+                            *size = share;
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(sizes)
+    }
+
+    /// Flip children end to start within a line, for `wrap_reverse`
+    ///
+    /// start and end alignment swap with it
+    fn mirror_cross_axis_range(
+        &mut self,
+        dim: Fin<ND>,
+        children: &[Id],
         offset: Scalar,
         space: Scalar,
     ) -> Result<(), ItemNotFound> {
-        if maybe_start_item_id == end_before_id {
-            return Ok(());
+        let mirror = offset + offset + space;
+        for &child_id in children {
+            let xx = self.item_rect_mut_err(child_id)?;
+            let position = &mut xx.position[dim.into_usize()];
+            *position = mirror - *position - xx.size[dim.into_usize()];
         }
+        Ok(())
+    }
+
+    /// Most space above and below baseline of children lined up by baseline
+    fn line_baseline(
+        &self,
+        dim: Fin<ND>,
+        line: &[Id],
+        inherited: Alignment,
+    ) -> Result<(Scalar, Scalar), ItemNotFound> {
+        let mut max_ascent = 0;
+        let mut max_descent = 0;
+        for &child_id in line {
+            let xx = self.item_rect_err(child_id)?;
+            if xx.item.flags.alignment_cross_axis(dim, inherited) != Alignment::Baseline {
+                continue;
+            }
+            let size = xx.size[dim.into_usize()];
+            let margin = xx.item.margins[dim.into_usize()];
+            let baseline = xx.baseline[dim.into_usize()].unwrap_or(size);
+            max_ascent = Scalar::max(max_ascent, margin.start + baseline);
+            max_descent = Scalar::max(max_descent, size - baseline + margin.end);
+        }
+        Ok((max_ascent, max_descent))
+    }
 
-        let Some(item_id) = maybe_start_item_id else { return Ok(()) };
+    /// Align children in a line across axis
+    ///
+    /// `ascent` is where the line's baseline is, from the line's start
+    ///
+    /// `flags_as_parent` gives alignment of children that don't set their own,
+    /// and what to do with those too big
+    fn arrange_cross_axis_range(
+        &mut self,
+        dim: Fin<ND>,
+        children: &[Id],
+        offset: Scalar,
+        space: Scalar,
+        ascent: Scalar,
+        flags_as_parent: &AsParentFlags<ND>,
+    ) -> Result<(), LayoutError> {
+        let Some((&item_id, rest)) = children.split_first() else { return Ok(()) };
+        let inherited = flags_as_parent.alignment_cross_axis[dim.into_usize()];
+        let overflow = flags_as_parent.overflow;
         let xx = self.item_rect_mut_err(item_id)?;
         let item = &mut xx.item;
         let position = &mut xx.position[dim.into_usize()];
         let size = &mut xx.size[dim.into_usize()];
-        let alignment = item.flags.as_child.alignment_cross_axis[dim.into_usize()].clone();
-        let margin = item.margins[dim.into_usize()].clone();
-        let next_sibling_id = item.next_sibling;
+        let baseline = xx.baseline[dim.into_usize()];
+        let alignment = item.flags.alignment_cross_axis(dim, inherited);
+        let margin = item.margins[dim.into_usize()];
 
         let max_size = Scalar::max(0, space - margin.start - margin.end);
 
-        match (alignment.front, alignment.back) {
-            // start
-            (true, false) => {
-                // IMPROVE: resize parent when remaining space too small
-                // or error out
-                *size = Scalar::min(*size, max_size);
+        // auto margins take the space left in the line, instead of alignment
+        if margin.auto_start || margin.auto_end {
+            *size = item.clamp_size(dim, fit(overflow, item_id, *size, max_size)?);
+            let space_left = max_size - *size;
+            *position = margin.start
+                + match (margin.auto_start, margin.auto_end) {
+                    (true, true) => space_left / 2,
+                    (true, false) => space_left,
+                    _ => 0,
+                }
+                + offset;
+            return self.arrange_cross_axis_range(
+                dim,
+                rest,
+                offset,
+                space,
+                ascent,
+                flags_as_parent,
+            );
+        }
+
+        match alignment {
+            Alignment::Start => {
+                *size = item.clamp_size(dim, fit(overflow, item_id, *size, max_size)?);
                 *position = margin.start;
             }
-            // center
-            (false, false) => {
-                *size = Scalar::min(*size, max_size);
+            Alignment::Center => {
+                *size = item.clamp_size(dim, fit(overflow, item_id, *size, max_size)?);
                 *position = (space - *size) / 2;
             }
-            // end
-            (false, true) => {
-                *size = Scalar::min(*size, max_size);
+            Alignment::End => {
+                *size = item.clamp_size(dim, fit(overflow, item_id, *size, max_size)?);
                 *position = space - *size - margin.end;
             }
-            // fill
-            (true, true) => {
-                *size = max_size;
+            // fill, unless size follows another dimension
+            Alignment::Fill => {
+                if item.aspect_ratio_source(dim).is_none() {
+                    *size = item.clamp_size(dim, max_size);
+                } else {
+                    *size = item.clamp_size(dim, fit(overflow, item_id, *size, max_size)?);
+                }
                 *position = margin.start;
             }
+            Alignment::Baseline => {
+                *size = item.clamp_size(dim, fit(overflow, item_id, *size, max_size)?);
+                *position = ascent - baseline.unwrap_or(*size);
+            }
         }
 
         *position += offset;
 
-        self.arrange_cross_axis_range(dim, next_sibling_id, end_before_id, offset, space)
+        self.arrange_cross_axis_range(dim, rest, offset, space, ascent, flags_as_parent)
+    }
+
+    /// Place children taken out of flow against the parent's box inside its padding
+    ///
+    /// the same box their relative sizes are resolved against
+    fn arrange_absolute(&mut self, parent_id: Id, dim: Fin<ND>) -> Result<(), ItemNotFound> {
+        let pxx = self.item_rect_err(parent_id)?;
+        let padding = pxx.item.padding[dim.into_usize()];
+        let offset = pxx.position[dim.into_usize()] + padding.start;
+        let space = pxx.size[dim.into_usize()] - padding.start - padding.end;
+
+        self.foreach_mut_children_rect(parent_id, |xx| {
+            let Some(insets) = xx.item.flags.as_child.absolute else { return Ok(()) };
+            let inset = insets[dim.into_usize()];
+            let margin = xx.item.margins[dim.into_usize()];
+            let position = &mut xx.position[dim.into_usize()];
+            let size = &mut xx.size[dim.into_usize()];
+
+            match (inset.start, inset.end) {
+                (Some(start), Some(end)) => {
+                    if !xx.item.flags.extra.fixed[dim.into_usize()] {
+                        let stretched = space - start - end - margin.start - margin.end;
+                        *size = xx.item.clamp_size(dim, Scalar::max(0, stretched));
+                    }
+                    *position = start + margin.start;
+                }
+                (Some(start), None) => {
+                    *position = start + margin.start;
+                }
+                (None, Some(end)) => {
+                    *position = space - end - margin.end - *size;
+                }
+                (None, None) => {
+                    *position = margin.start;
+                }
+            }
+            *position += offset;
+            Ok(())
+        })
     }
 
     // ======
@@ -537,6 +1252,42 @@ impl<const ND: usize> Context<ND> {
         Ok(())
     }
 
+    /// Children taking part in line building, by `order` then sibling order
+    fn flow_children(&self, parent_id: Id) -> Result<Vec<Id>, ItemNotFound> {
+        let parent = self.item_err(parent_id)?;
+        let mut maybe_child_id = parent.first_child;
+        let mut children = vec![];
+
+        while let Some(child_id) = maybe_child_id {
+            let child = self.item_err(child_id)?;
+            if child.flags.as_child.absolute.is_none() {
+                children.push((child.flags.as_child.order, child_id));
+            }
+            maybe_child_id = child.next_sibling;
+        }
+        // stable, so equal order keeps sibling order
+        children.sort_by_key(|&(order, _)| order);
+        Ok(children.into_iter().map(|(_, child_id)| child_id).collect())
+    }
+
+    /// Children back to front, by `z_index` then sibling order
+    ///
+    /// Paint in this order, and hit test in reverse
+    pub fn paint_order(&self, parent_id: Id) -> Result<Vec<Id>, ItemNotFound> {
+        let parent = self.item_err(parent_id)?;
+        let mut maybe_child_id = parent.first_child;
+        let mut children = vec![];
+
+        while let Some(child_id) = maybe_child_id {
+            let child = self.item_err(child_id)?;
+            children.push((child.flags.as_child.z_index, child_id));
+            maybe_child_id = child.next_sibling;
+        }
+        // stable, so equal z-index keeps sibling order
+        children.sort_by_key(|&(z_index, _)| z_index);
+        Ok(children.into_iter().map(|(_, child_id)| child_id).collect())
+    }
+
     pub fn item_mut_last_child(
         &mut self,
         parent_id: Id,
@@ -599,23 +1350,140 @@ impl<const ND: usize> Context<ND> {
     }
 }
 
+/// Space between neighbours from the margins facing each other
+///
+/// Collapsing takes the biggest positive plus the most negative margin
+/// join(true, 10, 5) => 10
+/// join(true, 10, -3) => 7
+/// join(true, -2, -3) => -3
+/// join(false, 10, -3) => 7
+fn join_margins(collapse: bool, margin_end: Scalar, margin_start: Scalar) -> Scalar {
+    if collapse {
+        let positive = Scalar::max(0, Scalar::max(margin_end, margin_start));
+        let negative = Scalar::min(0, Scalar::min(margin_end, margin_start));
+        positive + negative
+    } else {
+        margin_end + margin_start
+    }
+}
+
+/// Fit a child's size in the space it has, as parent's overflow says
+fn fit(
+    overflow: Overflow,
+    child_id: Id,
+    size: Scalar,
+    space: Scalar,
+) -> Result<Scalar, LayoutError> {
+    if size <= space {
+        return Ok(size);
+    }
+    match overflow {
+        Overflow::Clamp => Ok(space),
+        Overflow::Visible | Overflow::Grow => Ok(size),
+        Overflow::Error => Err(LayoutError::Overflow(child_id)),
+    }
+}
+
+/// Share a line among weighted pieces, pixel-perfect
+/// share(10, [1, 1]) => [5, 5]
+/// share(10, [1, 2]) => [4, 6]
+/// share(9, [0, 1, 1]) => [0, 5, 4]
+fn share_by_weight(space: Scalar, weights: &[u16]) -> Vec<Scalar> {
+    let total: i32 = weights.iter().map(|&w| w as i32).sum();
+    if total == 0 {
+        return vec![0; weights.len()];
+    }
+
+    let mut shares: Vec<Scalar> = weights
+        .iter()
+        .map(|&w| (space as i32 * w as i32 / total) as Scalar)
+        .collect();
+    let mut remaining = space - shares.iter().sum::<Scalar>();
+
+    // hand out leftover pixels one by one to the front
+    let step = remaining.signum();
+    for (share, &w) in shares.iter_mut().zip(weights) {
+        if remaining == 0 {
+            break;
+        }
+        if w > 0 {
+            *share += step;
+            remaining -= step;
+        }
+    }
+    shares
+}
+
 /// Chop a line into pixel-perfect pieces
 /// chop(X, 0) => []
-/// chop(X, X) => [X]
+/// chop(X, 1) => [X]
 /// chop(9, 2) => [5, 4]
 /// chop(18, 4) => [5, 4, 5, 4]
-fn enlarge_gaps_inbetween(gaps_before: &mut Vec<(Id, i16)>, extra_space: i16) {
+fn chop(space: Scalar, n: Scalar) -> Vec<Scalar> {
+    let space = space as i32;
+    let n = n as i32;
+    // where the i-th cut lands, rounded up
+    let cut = |i: i32| -((-space * i).div_euclid(n));
+
+    (0..n).map(|i| (cut(i + 1) - cut(i)) as Scalar).collect()
+}
+
+/// Spread extra space into the gaps between children
+fn enlarge_gaps_inbetween(gaps_before: &mut [Scalar], extra_space: Scalar) {
     let n = gaps_before.len() as Scalar;
     let n = if n >= 2 { n - 1 } else { return };
 
-    let segment = extra_space / n;
-    let extra_space = extra_space - segment * n;
+    for (gap_before, piece) in gaps_before[1..].iter_mut().zip(chop(extra_space, n)) {
+        *gap_before += piece;
+    }
+}
 
-    for i in 1..n+1 {
-        gaps_before[i as usize].1 += if i <= extra_space {
-            segment + 1
-        } else {
-            segment
-        };
+/// Share extra space into the gap before each piece, and the one after the last
+///
+/// With no space to share on an overflowing line, space-* fall back to start
+/// or center. Stretch leaves gaps alone, stretch the pieces before this.
+fn justify_gaps(alignment: Justify, gaps_before: &mut [Scalar], extra_space: Scalar) {
+    if gaps_before.is_empty() {
+        return;
+    }
+    let n = gaps_before.len() as Scalar;
+    match alignment {
+        Justify::Start | Justify::Stretch => {
+            // extra space in the end, do nothing
+        }
+        Justify::Center => {
+            gaps_before[0] += extra_space / 2;
+        }
+        Justify::End => {
+            // space all in front
+            gaps_before[0] += extra_space;
+        }
+        Justify::SpaceBetween => {
+            if extra_space > 0 {
+                enlarge_gaps_inbetween(gaps_before, extra_space);
+            }
+        }
+        Justify::SpaceAround => {
+            if extra_space > 0 {
+                // half a piece on both sides of each child
+                let pieces = chop(extra_space, n * 2);
+                gaps_before[0] += pieces[0];
+                for i in 1..gaps_before.len() {
+                    gaps_before[i] += pieces[i * 2 - 1] + pieces[i * 2];
+                }
+            } else {
+                gaps_before[0] += extra_space / 2;
+            }
+        }
+        Justify::SpaceEvenly => {
+            if extra_space > 0 {
+                let pieces = chop(extra_space, n + 1);
+                for (gap_before, piece) in gaps_before.iter_mut().zip(pieces) {
+                    *gap_before += piece;
+                }
+            } else {
+                gaps_before[0] += extra_space / 2;
+            }
+        }
     }
 }
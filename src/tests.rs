@@ -0,0 +1,892 @@
+use super::*;
+
+#[test]
+fn share_by_weight_hands_remainder_to_front() {
+    assert_eq!(share_by_weight(10, &[1, 1]), [5, 5]);
+    assert_eq!(share_by_weight(10, &[1, 2]), [4, 6]);
+    assert_eq!(share_by_weight(80, &[1, 2]), [27, 53]);
+    assert_eq!(share_by_weight(9, &[0, 1, 1]), [0, 5, 4]);
+    assert_eq!(share_by_weight(7, &[0, 0]), [0, 0]);
+}
+
+#[test]
+fn share_by_weight_shares_negative_space() {
+    assert_eq!(share_by_weight(-10, &[1, 2]), [-4, -6]);
+    assert_eq!(share_by_weight(-9, &[1, 0, 1]), [-5, 0, -4]);
+}
+
+fn dim(i: usize) -> Fin<2> {
+    i.try_into().unwrap()
+}
+
+/// A sized root, laid out by `set_up`
+fn root(ctx: &mut Context<2>, size: [Scalar; 2], set_up: impl FnOnce(&mut Item<2>)) -> Id {
+    ctx.item_new_mut(|item| {
+        item.size = size.map(Size::Px);
+        set_up(item);
+    })
+}
+
+fn child(ctx: &mut Context<2>, parent: Id, set_up: impl FnOnce(&mut Item<2>)) -> Id {
+    let id = ctx.item_new_mut(set_up);
+    ctx.push_back(parent, id).unwrap();
+    id
+}
+
+/// Flex row that neither wraps nor stretches across
+fn row(ctx: &mut Context<2>, width: Scalar) -> Id {
+    root(ctx, [width, 100], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.auto_wrap = false;
+    })
+}
+
+fn position(ctx: &Context<2>, id: Id) -> [Scalar; 2] {
+    ctx.item_rect_err(id).unwrap().position
+}
+
+fn size(ctx: &Context<2>, id: Id) -> [Scalar; 2] {
+    ctx.item_rect_err(id).unwrap().size
+}
+
+#[test]
+fn flex_line_grows_by_weight() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let a = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(10);
+        item.flags.as_child.grow = 1;
+    });
+    let b = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(10);
+        item.flags.as_child.grow = 2;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, a)[0], 37);
+    assert_eq!(size(&ctx, b)[0], 63);
+    assert_eq!(position(&ctx, b)[0], 37);
+}
+
+#[test]
+fn flex_line_shrinks_by_weight() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let a = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(60);
+        item.flags.as_child.shrink = 1;
+    });
+    let b = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(70);
+        item.flags.as_child.shrink = 2;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, a)[0], 50);
+    assert_eq!(size(&ctx, b)[0], 50);
+}
+
+#[test]
+fn flex_line_passes_on_share_of_child_at_max_size() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let children: Vec<Id> = (0..3)
+        .map(|i| {
+            child(&mut ctx, parent, |item| {
+                item.basis[0] = Some(10);
+                item.flags.as_child.grow = 1;
+                if i == 0 {
+                    item.max_size[0] = Some(20);
+                }
+            })
+        })
+        .collect();
+    ctx.layout_item_recursively(parent).unwrap();
+
+    let sizes: Vec<Scalar> = children.iter().map(|&id| size(&ctx, id)[0]).collect();
+    assert_eq!(sizes, [20, 40, 40]);
+}
+
+#[test]
+fn flex_line_passes_on_share_of_child_at_min_size() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let a = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(60);
+        item.min_size[0] = Some(55);
+        item.flags.as_child.shrink = 1;
+    });
+    let b = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(60);
+        item.flags.as_child.shrink = 1;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, a)[0], 55);
+    assert_eq!(size(&ctx, b)[0], 45);
+}
+
+#[test]
+fn flex_line_stops_shrinking_at_zero() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 50);
+    let a = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(10);
+        item.flags.as_child.shrink = 1;
+    });
+    let b = child(&mut ctx, parent, |item| {
+        item.basis[0] = Some(100);
+        item.flags.as_child.shrink = 1;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, a)[0], 0);
+    assert_eq!(size(&ctx, b)[0], 50);
+}
+
+/// Same insets in both dimensions
+fn absolute(start: Option<Scalar>, end: Option<Scalar>) -> Option<[Inset; 2]> {
+    Some([Inset { start, end }; 2])
+}
+
+#[test]
+fn absolute_insets_measure_from_inside_padding() {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [100, 100], |item| {
+        item.padding[0] = Padding { start: 10, end: 20 };
+    });
+    let full = child(&mut ctx, parent, |item| {
+        item.size[0] = Size::Percent(100);
+        item.flags.as_child.absolute = absolute(Some(0), None);
+    });
+    let stretched = child(&mut ctx, parent, |item| {
+        item.flags.as_child.absolute = absolute(Some(5), Some(5));
+    });
+    let at_end = child(&mut ctx, parent, |item| {
+        item.size[0] = Size::Px(10);
+        item.flags.as_child.absolute = absolute(None, Some(0));
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, full)[0], 10);
+    assert_eq!(size(&ctx, full)[0], 70);
+    assert_eq!(position(&ctx, stretched)[0], 15);
+    assert_eq!(size(&ctx, stretched)[0], 60);
+    assert_eq!(position(&ctx, at_end)[0], 70);
+}
+
+#[test]
+fn chop_cuts_pixel_perfect_pieces() {
+    assert_eq!(chop(5, 0), []);
+    assert_eq!(chop(5, 1), [5]);
+    assert_eq!(chop(9, 2), [5, 4]);
+    assert_eq!(chop(18, 4), [5, 4, 5, 4]);
+    assert_eq!(chop(11, 6), [2, 2, 2, 2, 2, 1]);
+    assert_eq!(chop(-9, 2), [-4, -5]);
+}
+
+/// Gaps before 3 pieces, given no gaps to start with
+fn justified(alignment: Justify, extra_space: Scalar) -> [Scalar; 3] {
+    let mut gaps_before = [0; 3];
+    justify_gaps(alignment, &mut gaps_before, extra_space);
+    gaps_before
+}
+
+#[test]
+fn justify_gaps_shares_uneven_space() {
+    assert_eq!(justified(Justify::Start, 11), [0, 0, 0]);
+    assert_eq!(justified(Justify::End, 11), [11, 0, 0]);
+    assert_eq!(justified(Justify::Center, 11), [5, 0, 0]);
+    assert_eq!(justified(Justify::SpaceBetween, 11), [0, 6, 5]);
+    // 1 left after the last piece
+    assert_eq!(justified(Justify::SpaceAround, 11), [2, 4, 4]);
+    // 2 left after the last piece
+    assert_eq!(justified(Justify::SpaceEvenly, 11), [3, 3, 3]);
+    // pieces stretch instead
+    assert_eq!(justified(Justify::Stretch, 11), [0, 0, 0]);
+}
+
+#[test]
+fn justify_gaps_on_overflowing_line() {
+    assert_eq!(justified(Justify::Start, -7), [0, 0, 0]);
+    assert_eq!(justified(Justify::End, -7), [-7, 0, 0]);
+    assert_eq!(justified(Justify::Center, -7), [-3, 0, 0]);
+    assert_eq!(justified(Justify::SpaceBetween, -7), [0, 0, 0]);
+    assert_eq!(justified(Justify::SpaceAround, -7), [-3, 0, 0]);
+    assert_eq!(justified(Justify::SpaceEvenly, -7), [-3, 0, 0]);
+    assert_eq!(justified(Justify::Stretch, -7), [0, 0, 0]);
+}
+
+#[test]
+fn justify_positions_children_on_line() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 71);
+    let flags_as_parent = &mut ctx.item_mut(parent).unwrap().flags.as_parent;
+    flags_as_parent.alignment_along_axis = Justify::SpaceEvenly;
+    let children: Vec<Id> = (0..3)
+        .map(|_| child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]))
+        .collect();
+    ctx.layout_item_recursively(parent).unwrap();
+
+    let positions: Vec<Scalar> = children.iter().map(|&id| position(&ctx, id)[0]).collect();
+    assert_eq!(positions, [3, 26, 49]);
+}
+
+#[test]
+fn wrap_reverse_mirrors_single_line() {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [100, 100], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.wrap_reverse = true;
+    });
+    let start = child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]);
+    let end = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20), Size::Px(10)];
+        item.flags.as_child.alignment_cross_axis[1] = Some(Alignment::End);
+    });
+    let fill = child(&mut ctx, parent, |item| {
+        item.size[0] = Size::Px(20);
+        item.margins[1].start = 5;
+        item.flags.as_child.alignment_cross_axis[1] = Some(Alignment::Fill);
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, start)[1], 80);
+    assert_eq!(position(&ctx, end)[1], 0);
+    assert_eq!(position(&ctx, fill)[1], 0);
+    assert_eq!(size(&ctx, fill)[1], 95);
+}
+
+#[test]
+fn wrap_reverse_mirrors_each_line() {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [100, 100], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.wrap_reverse = true;
+    });
+    let tall = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20), Size::Px(30)]
+    });
+    let start = child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]);
+    let end = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20); 2];
+        item.flags.as_child.wrap_me = true;
+        item.flags.as_child.alignment_cross_axis[1] = Some(Alignment::End);
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    // first line in 70..100, second in 50..70
+    assert_eq!(position(&ctx, tall)[1], 70);
+    assert_eq!(position(&ctx, start)[1], 80);
+    assert_eq!(position(&ctx, end)[1], 50);
+}
+
+/// Auto sized flex container of five 20px squares, in a sized root
+fn wrapping_five(ctx: &mut Context<2>, root_size: [Scalar; 2], along_dim: usize) -> (Id, Id) {
+    let parent = root(ctx, root_size, |_| {});
+    let container = child(ctx, parent, |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(along_dim));
+    });
+    for _ in 0..5 {
+        child(ctx, container, |item| item.size = [Size::Px(20); 2]);
+    }
+    (parent, container)
+}
+
+#[test]
+fn auto_wrap_column_relays_out_cross_axis() {
+    let mut ctx = Context::new();
+    let (parent, column) = wrapping_five(&mut ctx, [100, 50], 1);
+    ctx.layout_item_recursively(parent).unwrap();
+
+    // breaks along dim 1 are only found after dim 0 was laid out
+    assert_eq!(size(&ctx, column), [60, 40]);
+    let children = ctx.flow_children(column).unwrap();
+    let positions: Vec<[Scalar; 2]> = children.iter().map(|&id| position(&ctx, id)).collect();
+    assert_eq!(positions, [[0, 0], [0, 20], [20, 0], [20, 20], [40, 0]]);
+}
+
+#[test]
+fn auto_wrap_row_measures_within_sized_ancestor() {
+    let mut ctx = Context::new();
+    let (parent, row) = wrapping_five(&mut ctx, [50, 100], 0);
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, row), [40, 60]);
+    let children = ctx.flow_children(row).unwrap();
+    let positions: Vec<[Scalar; 2]> = children.iter().map(|&id| position(&ctx, id)).collect();
+    assert_eq!(positions, [[0, 0], [20, 0], [0, 20], [20, 20], [0, 40]]);
+}
+
+#[test]
+fn auto_wrap_breaks_before_child_that_overflows() {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [50, 100], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+    });
+    let a = child(&mut ctx, parent, |item| item.size = [Size::Px(30); 2]);
+    let b = child(&mut ctx, parent, |item| item.size = [Size::Px(30); 2]);
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, a), [0, 0]);
+    assert_eq!(position(&ctx, b), [0, 30]);
+}
+
+/// Two wrapped lines of 20px squares with 10px margins across axis
+fn two_lines_with_margins(collapse_margins: bool) -> Scalar {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [30, 100], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.collapse_margins = collapse_margins;
+    });
+    let children: Vec<Id> = (0..2)
+        .map(|_| {
+            child(&mut ctx, parent, |item| {
+                item.size = [Size::Px(20); 2];
+                item.margins[1] = Margin {
+                    start: 10,
+                    end: 10,
+                    ..Default::default()
+                };
+            })
+        })
+        .collect();
+    ctx.layout_item_recursively(parent).unwrap();
+    assert_eq!(position(&ctx, children[0])[1], 10);
+    position(&ctx, children[1])[1]
+}
+
+#[test]
+fn wrapped_lines_collapse_margins_by_default() {
+    assert_eq!(two_lines_with_margins(true), 40);
+    assert_eq!(two_lines_with_margins(false), 50);
+}
+
+#[test]
+fn join_margins_with_negative_margins() {
+    assert_eq!(join_margins(true, 10, 5), 10);
+    assert_eq!(join_margins(true, 10, -3), 7);
+    assert_eq!(join_margins(true, -2, -3), -3);
+    assert_eq!(join_margins(false, 10, 5), 15);
+    assert_eq!(join_margins(false, 10, -3), 7);
+    assert_eq!(join_margins(false, -2, -3), -5);
+}
+
+/// Position of the second of two 30px children overlapping by margins
+fn overlapped(collapse_margins: bool, margin_end: Scalar, margin_start: Scalar) -> Scalar {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let flags_as_parent = &mut ctx.item_mut(parent).unwrap().flags.as_parent;
+    flags_as_parent.collapse_margins = collapse_margins;
+    child(&mut ctx, parent, |item| {
+        item.size[0] = Size::Px(30);
+        item.margins[0].end = margin_end;
+    });
+    let b = child(&mut ctx, parent, |item| {
+        item.size[0] = Size::Px(30);
+        item.margins[0].start = margin_start;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+    position(&ctx, b)[0]
+}
+
+#[test]
+fn negative_margins_overlap_along_axis() {
+    assert_eq!(overlapped(true, 5, -15), 20);
+    assert_eq!(overlapped(true, -5, -10), 20);
+    assert_eq!(overlapped(false, 5, -15), 20);
+    assert_eq!(overlapped(false, -5, -10), 15);
+}
+
+#[test]
+fn negative_leading_margin_sticks_out_of_parent() {
+    for collapse_margins in [true, false] {
+        let mut ctx = Context::new();
+        let parent = row(&mut ctx, 100);
+        let flags_as_parent = &mut ctx.item_mut(parent).unwrap().flags.as_parent;
+        flags_as_parent.collapse_margins = collapse_margins;
+        let a = child(&mut ctx, parent, |item| {
+            item.size[0] = Size::Px(30);
+            item.margins[0].start = -10;
+        });
+        let b = child(&mut ctx, parent, |item| item.size[0] = Size::Px(30));
+        ctx.layout_item_recursively(parent).unwrap();
+
+        assert_eq!(position(&ctx, a)[0], -10);
+        assert_eq!(position(&ctx, b)[0], 20);
+    }
+}
+
+#[test]
+fn fill_stretches_over_negative_cross_margins() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let a = child(&mut ctx, parent, |item| {
+        item.size[0] = Size::Px(30);
+        item.margins[1] = Margin {
+            start: -5,
+            end: -10,
+            ..Default::default()
+        };
+        item.flags.as_child.alignment_cross_axis[1] = Some(Alignment::Fill);
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, a)[1], -5);
+    assert_eq!(size(&ctx, a)[1], 115);
+}
+
+#[test]
+fn negative_margins_measure_content_no_less_than_zero() {
+    let mut ctx = Context::new();
+    let parent = ctx.item_new_mut(|item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.padding[0] = Padding { start: 2, end: 3 };
+    });
+    child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(10); 2];
+        item.margins[0].start = -30;
+        item.margins[1].end = -15;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, parent), [5, 0]);
+}
+
+/// Positions along dim 0 of 20px children in a row of 100
+fn row_positions(
+    set_up_row: impl FnOnce(&mut Item<2>),
+    set_ups: &[&dyn Fn(&mut Item<2>)],
+) -> Vec<Scalar> {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    set_up_row(ctx.item_mut(parent).unwrap());
+    let children: Vec<Id> = set_ups
+        .iter()
+        .map(|set_up| {
+            child(&mut ctx, parent, |item| {
+                item.size = [Size::Px(20); 2];
+                set_up(item);
+            })
+        })
+        .collect();
+    ctx.layout_item_recursively(parent).unwrap();
+    children.iter().map(|&id| position(&ctx, id)[0]).collect()
+}
+
+#[test]
+fn reverse_along_axis_lays_out_from_end() {
+    let reverse = |item: &mut Item<2>| item.flags.as_parent.reverse_along_axis = true;
+    let plain = |_: &mut Item<2>| {};
+    let margin_end = |item: &mut Item<2>| item.margins[0].end = 5;
+    assert_eq!(
+        row_positions(reverse, &[&plain, &plain, &plain]),
+        [80, 60, 40]
+    );
+    // physical margins stay on their side
+    assert_eq!(row_positions(reverse, &[&margin_end, &plain]), [75, 55]);
+
+    let reverse_end = |item: &mut Item<2>| {
+        item.flags.as_parent.reverse_along_axis = true;
+        item.flags.as_parent.alignment_along_axis = Justify::End;
+    };
+    assert_eq!(row_positions(reverse_end, &[&plain, &plain]), [20, 0]);
+}
+
+#[test]
+fn order_sorts_line_but_keeps_ties_and_paint_order() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let children: Vec<Id> = [2, 0, 1, 0]
+        .into_iter()
+        .map(|order| {
+            child(&mut ctx, parent, |item| {
+                item.size = [Size::Px(20); 2];
+                item.flags.as_child.order = order;
+            })
+        })
+        .collect();
+    ctx.layout_item_recursively(parent).unwrap();
+
+    let positions: Vec<Scalar> = children.iter().map(|&id| position(&ctx, id)[0]).collect();
+    assert_eq!(positions, [60, 0, 40, 20]);
+    assert_eq!(ctx.paint_order(parent).unwrap(), children);
+}
+
+/// Position and size across axis of the first child on each line,
+/// with 20px squares two to a line in a 40x100 row
+fn line_boxes(alignment_lines: Justify, count: usize) -> Vec<(Scalar, Scalar)> {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [40, 100], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.alignment_lines = alignment_lines;
+        item.flags.as_parent.alignment_cross_axis[1] = Alignment::Fill;
+    });
+    let children: Vec<Id> = (0..count)
+        .map(|_| child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]))
+        .collect();
+    ctx.layout_item_recursively(parent).unwrap();
+    children
+        .iter()
+        .step_by(2)
+        .map(|&id| (position(&ctx, id)[1], size(&ctx, id)[1]))
+        .collect()
+}
+
+#[test]
+fn alignment_lines_shares_space_between_lines() {
+    // 60 left over
+    assert_eq!(line_boxes(Justify::Start, 4), [(0, 20), (20, 20)]);
+    assert_eq!(line_boxes(Justify::Center, 4), [(30, 20), (50, 20)]);
+    assert_eq!(line_boxes(Justify::SpaceAround, 4), [(15, 20), (65, 20)]);
+    assert_eq!(line_boxes(Justify::Stretch, 4), [(0, 50), (50, 50)]);
+    // a single line takes the whole space
+    assert_eq!(line_boxes(Justify::Center, 2), [(0, 100)]);
+}
+
+/// 20px wide child lined up by baseline, 40px tall
+fn with_baseline(ctx: &mut Context<2>, parent: Id, baseline: Scalar) -> Id {
+    child(ctx, parent, |item| {
+        item.size = [Size::Px(20), Size::Px(40)];
+        item.baseline[1] = Some(baseline);
+    })
+}
+
+/// Row lining up children by baseline
+fn baseline_row(ctx: &mut Context<2>, parent: Option<Id>) -> Id {
+    let set_up = |item: &mut Item<2>| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.alignment_cross_axis[1] = Alignment::Baseline;
+    };
+    match parent {
+        Some(parent) => child(ctx, parent, set_up),
+        None => root(ctx, [200, 200], set_up),
+    }
+}
+
+#[test]
+fn baseline_lines_up_children() {
+    let mut ctx = Context::new();
+    let parent = baseline_row(&mut ctx, None);
+    let a = with_baseline(&mut ctx, parent, 10);
+    let b = with_baseline(&mut ctx, parent, 30);
+    // no baseline, lined up by its end edge
+    let c = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20), Size::Px(25)]
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, a)[1], 20);
+    assert_eq!(position(&ctx, b)[1], 0);
+    assert_eq!(position(&ctx, c)[1], 5);
+}
+
+#[test]
+fn baseline_lines_up_across_nesting() {
+    let mut ctx = Context::new();
+    let outer = baseline_row(&mut ctx, None);
+    let inner = baseline_row(&mut ctx, Some(outer));
+    ctx.item_mut(inner).unwrap().padding[1].start = 5;
+    let a = with_baseline(&mut ctx, inner, 10);
+    let b = with_baseline(&mut ctx, inner, 30);
+    let sibling = with_baseline(&mut ctx, outer, 30);
+    ctx.layout_item_recursively(outer).unwrap();
+
+    assert_eq!(ctx.item_rect_err(inner).unwrap().baseline[1], Some(35));
+    assert_eq!(position(&ctx, a)[1] + 10, 35);
+    assert_eq!(position(&ctx, b)[1] + 30, 35);
+    assert_eq!(position(&ctx, sibling)[1] + 30, 35);
+}
+
+#[test]
+fn children_inherit_cross_axis_alignment() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    ctx.item_mut(parent)
+        .unwrap()
+        .flags
+        .as_parent
+        .alignment_cross_axis[1] = Alignment::Center;
+    let inherits = child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]);
+    let overrides = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20); 2];
+        item.flags.as_child.alignment_cross_axis[1] = Some(Alignment::End);
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, inherits)[1], 40);
+    assert_eq!(position(&ctx, overrides)[1], 80);
+}
+
+#[test]
+fn auto_margins_take_space_left() {
+    let plain = |_: &mut Item<2>| {};
+    let auto_start = |item: &mut Item<2>| item.margins[0].auto_start = true;
+    let auto_both = |item: &mut Item<2>| {
+        item.margins[0].auto_start = true;
+        item.margins[0].auto_end = true;
+    };
+    // pushed to the far end, 40 left
+    assert_eq!(
+        row_positions(|_| {}, &[&plain, &plain, &auto_start]),
+        [0, 20, 80]
+    );
+    // centered, and alignment gets nothing
+    let justify_end = |item: &mut Item<2>| item.flags.as_parent.alignment_along_axis = Justify::End;
+    assert_eq!(row_positions(justify_end, &[&auto_both]), [40]);
+    // two auto margins share 60
+    assert_eq!(row_positions(|_| {}, &[&auto_both, &plain]), [30, 80]);
+}
+
+#[test]
+fn auto_margins_place_child_across_axis() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 100);
+    let centered = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20); 2];
+        item.margins[1].auto_start = true;
+        item.margins[1].auto_end = true;
+        item.flags.as_child.alignment_cross_axis[1] = Some(Alignment::Fill);
+    });
+    let at_end = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20); 2];
+        item.margins[1] = Margin {
+            start: 0,
+            end: 5,
+            auto_start: true,
+            auto_end: false,
+        };
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, centered)[1], 40);
+    assert_eq!(size(&ctx, centered)[1], 20);
+    assert_eq!(position(&ctx, at_end)[1], 75);
+}
+
+/// Row of 50x20 holding `count` shrinking children, 30px along and across axis
+fn overflowing_row(
+    overflow: Overflow,
+    count: usize,
+) -> (Result<(), LayoutError>, Context<2>, Vec<Id>) {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [50, 20], |item| {
+        item.flags.as_parent.layout = Layout::Flex(dim(0));
+        item.flags.as_parent.auto_wrap = false;
+        item.flags.as_parent.overflow = overflow;
+    });
+    let children: Vec<Id> = (0..count)
+        .map(|_| {
+            child(&mut ctx, parent, |item| {
+                item.size = [Size::Auto, Size::Px(30)];
+                item.basis[0] = Some(30);
+                item.flags.as_child.shrink = 1;
+            })
+        })
+        .collect();
+    let result = ctx.layout_item_recursively(parent);
+    (result, ctx, children)
+}
+
+#[test]
+fn overflow_clamp_shrinks_children() {
+    let (result, ctx, children) = overflowing_row(Overflow::Clamp, 2);
+    assert!(result.is_ok());
+    assert_eq!(size(&ctx, children[0]), [25, 20]);
+    assert_eq!(size(&ctx, children[1]), [25, 20]);
+}
+
+#[test]
+fn overflow_visible_keeps_size() {
+    let (result, ctx, children) = overflowing_row(Overflow::Visible, 2);
+    assert!(result.is_ok());
+    assert_eq!(size(&ctx, children[0]), [30, 30]);
+    assert_eq!(position(&ctx, children[1]), [30, 0]);
+}
+
+#[test]
+fn overflow_error_names_child_across_axis() {
+    let (result, _, children) = overflowing_row(Overflow::Error, 1);
+    assert!(matches!(result, Err(LayoutError::Overflow(id)) if id == children[0]));
+}
+
+#[test]
+fn overflow_error_names_child_along_axis() {
+    let mut ctx = Context::new();
+    let parent = row(&mut ctx, 50);
+    ctx.item_mut(parent).unwrap().flags.as_parent.overflow = Overflow::Error;
+    let children: Vec<Id> = (0..3)
+        .map(|_| child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]))
+        .collect();
+    let result = ctx.layout_item_recursively(parent);
+    assert!(matches!(result, Err(LayoutError::Overflow(id)) if id == children[2]));
+}
+
+#[test]
+fn stack_sizes_to_biggest_child() {
+    let mut ctx = Context::new();
+    let parent = ctx.item_new_mut(|item| item.flags.as_parent.layout = Layout::Stack);
+    child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(30), Size::Px(10)]
+    });
+    child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(10), Size::Px(20)];
+        item.margins[1].start = 5;
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(size(&ctx, parent), [30, 25]);
+}
+
+#[test]
+fn stack_aligns_children_per_axis() {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [100, 50], |item| {
+        item.flags.as_parent.layout = Layout::Stack;
+        item.flags.as_parent.alignment_cross_axis = [Alignment::End, Alignment::Center];
+    });
+    let a = child(&mut ctx, parent, |item| item.size = [Size::Px(20); 2]);
+    let b = child(&mut ctx, parent, |item| {
+        item.size = [Size::Px(20); 2];
+        item.flags.as_child.alignment_cross_axis = [Some(Alignment::Start), Some(Alignment::Fill)];
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, a), [80, 15]);
+    assert_eq!(position(&ctx, b), [0, 0]);
+    assert_eq!(size(&ctx, b), [20, 50]);
+}
+
+#[test]
+fn paint_order_sorts_by_z_index_keeping_ties() {
+    let mut ctx = Context::new();
+    let parent = root(&mut ctx, [100, 100], |item| {
+        item.flags.as_parent.layout = Layout::Stack
+    });
+    let z = |z_index| move |item: &mut Item<2>| item.flags.as_child.z_index = z_index;
+    let a = child(&mut ctx, parent, z(1));
+    let b = child(&mut ctx, parent, z(0));
+    let c = child(&mut ctx, parent, z(1));
+    let d = child(&mut ctx, parent, |item| {
+        item.flags.as_child.absolute = absolute(Some(0), None);
+        item.flags.as_child.z_index = -1;
+    });
+    let e = child(&mut ctx, parent, z(0));
+
+    assert_eq!(ctx.paint_order(parent).unwrap(), [d, b, e, a, c]);
+}
+
+/// Grid of `size` with `columns` and one 20px row
+fn grid(ctx: &mut Context<2>, size: [Scalar; 2], columns: Vec<Track>) -> Id {
+    root(ctx, size, |item| {
+        item.flags.as_parent.layout = Layout::Grid([columns, vec![Track::Px(20)]]);
+    })
+}
+
+/// Child of `width` covering `span` columns from `line`
+fn grid_child(ctx: &mut Context<2>, parent: Id, line: u16, span: u16, width: Scalar) -> Id {
+    child(ctx, parent, |item| {
+        item.size = [Size::Px(width), Size::Px(20)];
+        item.flags.as_child.grid_area[0] = GridPlacement { line, span };
+    })
+}
+
+#[test]
+fn grid_fraction_tracks_leave_room_for_big_content() {
+    let mut ctx = Context::new();
+    let parent = grid(&mut ctx, [100, 20], vec![Track::Fraction(1); 2]);
+    grid_child(&mut ctx, parent, 0, 1, 80);
+    let b = grid_child(&mut ctx, parent, 1, 1, 10);
+    ctx.layout_item_recursively(parent).unwrap();
+
+    let tracks = [Track::Fraction(1); 2];
+    assert_eq!(
+        ctx.grid_tracks(parent, dim(0), &tracks, Some(100)).unwrap(),
+        [80, 20]
+    );
+    assert_eq!(position(&ctx, b), [80, 0]);
+}
+
+#[test]
+fn grid_sizes_px_auto_and_fraction_tracks() {
+    let mut ctx = Context::new();
+    let columns = vec![
+        Track::Px(10),
+        Track::Auto,
+        Track::Fraction(1),
+        Track::Fraction(3),
+    ];
+    let parent = grid(&mut ctx, [110, 20], columns);
+    // bigger than its track, which doesn't grow
+    let px = grid_child(&mut ctx, parent, 0, 1, 15);
+    let auto = grid_child(&mut ctx, parent, 1, 1, 20);
+    let small = grid_child(&mut ctx, parent, 2, 1, 5);
+    let big = grid_child(&mut ctx, parent, 3, 1, 5);
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, px)[0], 0);
+    assert_eq!(position(&ctx, auto)[0], 10);
+    assert_eq!(position(&ctx, small)[0], 30);
+    assert_eq!(position(&ctx, big)[0], 50);
+}
+
+#[test]
+fn grid_places_children_by_line_and_span() {
+    let mut ctx = Context::new();
+    let columns = vec![Track::Px(20), Track::Px(30), Track::Px(40)];
+    let parent = grid(&mut ctx, [90, 20], columns);
+    ctx.item_mut(parent)
+        .unwrap()
+        .flags
+        .as_parent
+        .alignment_cross_axis[0] = Alignment::Fill;
+    let a = child(&mut ctx, parent, |item| {
+        item.size[1] = Size::Px(20);
+        item.flags.as_child.grid_area[0] = GridPlacement { line: 1, span: 2 };
+    });
+    let b = child(&mut ctx, parent, |item| {
+        item.size[1] = Size::Px(20);
+        item.flags.as_child.grid_area[0] = GridPlacement { line: 2, span: 0 };
+    });
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(position(&ctx, a), [20, 0]);
+    assert_eq!(size(&ctx, a), [70, 20]);
+    assert_eq!(position(&ctx, b), [50, 0]);
+    assert_eq!(size(&ctx, b), [40, 20]);
+}
+
+#[test]
+fn grid_adds_auto_tracks_for_children_past_the_end() {
+    let mut ctx = Context::new();
+    let parent = ctx.item_new_mut(|item| {
+        item.flags.as_parent.layout = Layout::Grid([vec![Track::Px(10)], vec![]]);
+    });
+    let a = grid_child(&mut ctx, parent, 2, 1, 15);
+    ctx.layout_item_recursively(parent).unwrap();
+
+    // empty implicit track between takes no space
+    assert_eq!(position(&ctx, a), [10, 0]);
+    assert_eq!(size(&ctx, parent), [25, 20]);
+}
+
+#[test]
+fn grid_places_children_in_three_dimensions() {
+    let mut ctx = Context::<3>::new();
+    let parent = ctx.item_new_mut(|item| {
+        item.size = [Size::Px(30), Size::Px(5), Size::Px(40)];
+        item.flags.as_parent.layout = Layout::Grid([
+            vec![Track::Px(10), Track::Px(20)],
+            vec![Track::Px(5)],
+            vec![Track::Fraction(1); 2],
+        ]);
+    });
+    let a = ctx.item_new_mut(|item| {
+        item.size = [Size::Px(5); 3];
+        item.flags.as_child.grid_area = [1, 0, 1].map(|line| GridPlacement { line, span: 1 });
+    });
+    ctx.push_back(parent, a).unwrap();
+    ctx.layout_item_recursively(parent).unwrap();
+
+    assert_eq!(ctx.item_rect_err(a).unwrap().position, [10, 0, 20]);
+}